@@ -0,0 +1,370 @@
+use crate::config::ParserConfig;
+use crate::error::{Error, ErrorType};
+use crate::header::{is_quoted_string, is_token, split_quoted, Header, HeaderMap};
+use crate::message;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+enum State {
+    #[default]
+    Size,
+    Data(usize),
+    DataEnd,
+    Trailers,
+    Done,
+}
+
+/// An incremental decoder for bodies sent with `Transfer-Encoding: chunked`. Bytes are fed in as
+/// they arrive, and the decoder reassembles the body and collects any trailer headers.
+///
+/// Chunk extensions are accepted but ignored. Chunk-size and trailer lines are limited to
+/// `max_header_len` bytes, and the trailers to `max_headers` lines and `max_head_len` bytes, as
+/// for the header section.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ChunkedDecoder {
+    state: State,
+    body: Vec<u8>,
+    trailers: HeaderMap,
+    trailers_len: usize,
+    config: ParserConfig,
+}
+
+impl ChunkedDecoder {
+    /// Create a new decoder, positioned at the start of the first chunk.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new decoder with the limits of the given config.
+    pub fn with_config(config: &ParserConfig) -> Self {
+        ChunkedDecoder {
+            config: config.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Feed bytes into the decoder, returning the number of bytes consumed. Bytes belonging to
+    /// an incomplete chunk-size or trailer line are not consumed, and should be fed in again
+    /// along with the bytes that follow. Once the decoder is done, no more bytes are consumed.
+    pub fn feed(&mut self, input: &[u8]) -> Result<usize, Error> {
+        let chunk_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid chunked encoding".to_string(),
+        };
+
+        let size_line_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: format!(
+                "Chunk size line longer than {} bytes",
+                self.config.max_header_len
+            ),
+        };
+
+        let count_err = Error {
+            err_type: ErrorType::TooManyHeaders,
+            msg: format!("More than {} trailers", self.config.max_headers),
+        };
+
+        let line_err = Error {
+            err_type: ErrorType::HeaderTooLarge,
+            msg: format!("Trailer longer than {} bytes", self.config.max_header_len),
+        };
+
+        let head_err = Error {
+            err_type: ErrorType::HeadersTooLarge,
+            msg: format!("Trailers longer than {} bytes", self.config.max_head_len),
+        };
+
+        let limit = self.config.max_header_len;
+        let mut pos = 0;
+
+        loop {
+            match self.state {
+                State::Size => {
+                    let line = match read_line(&input[pos..], limit) {
+                        Some(line) => line,
+                        None if input.len() - pos < limit + 2 => return Ok(pos),
+                        None => return Err(size_line_err),
+                    };
+                    pos += line.len() + 2;
+
+                    let size = Self::parse_size(line)?;
+
+                    self.state = match size {
+                        0 => State::Trailers,
+                        size => State::Data(size),
+                    };
+                }
+                State::Data(remaining) => {
+                    let available = remaining.min(input.len() - pos);
+
+                    self.body.extend_from_slice(&input[pos..pos + available]);
+                    pos += available;
+
+                    if available < remaining {
+                        self.state = State::Data(remaining - available);
+                        return Ok(pos);
+                    }
+
+                    self.state = State::DataEnd;
+                }
+                State::DataEnd => {
+                    if input.len() - pos < 2 {
+                        return Ok(pos);
+                    }
+
+                    if &input[pos..pos + 2] != b"\r\n" {
+                        return Err(chunk_err);
+                    }
+                    pos += 2;
+
+                    self.state = State::Size;
+                }
+                State::Trailers => {
+                    let line = match read_line(&input[pos..], limit) {
+                        Some(line) => line,
+                        None if input.len() - pos < limit + 2 => return Ok(pos),
+                        None => return Err(line_err),
+                    };
+                    pos += line.len() + 2;
+
+                    if line.is_empty() {
+                        self.state = State::Done;
+                        continue;
+                    }
+
+                    self.trailers_len += line.len() + 2;
+
+                    if self.trailers.len() >= self.config.max_headers {
+                        return Err(count_err);
+                    }
+
+                    if self.trailers_len > self.config.max_head_len {
+                        return Err(head_err);
+                    }
+
+                    let line: String = line.iter().map(|&x| x as char).collect();
+                    self.trailers.append(Header::parse(&line)?);
+                }
+                State::Done => return Ok(pos),
+            }
+        }
+    }
+
+    /// Whether the final chunk and trailer section have been received.
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Get the length of the body decoded so far.
+    pub(crate) fn body_len(&self) -> usize {
+        self.body.len()
+    }
+
+    /// Consume the decoder, returning the decoded body and the trailer headers.
+    pub fn into_parts(self) -> (Vec<u8>, HeaderMap) {
+        (self.body, self.trailers)
+    }
+
+    fn parse_size(line: &[u8]) -> Result<usize, Error> {
+        let size_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid chunk size".to_string(),
+        };
+
+        // Anything after a `;` is a chunk extension. Whitespace is only allowed before the `;`,
+        // since lenient chunk size parsing can be used to smuggle requests.
+        let size = match line.iter().position(|&x| x == b';') {
+            Some(index) => {
+                let end = line[..index]
+                    .iter()
+                    .rposition(|&x| x != b' ' && x != b'\t')
+                    .map_or(0, |x| x + 1);
+
+                if !is_chunk_ext(&line[index..]) {
+                    return Err(size_err);
+                }
+
+                &line[..end]
+            }
+            None => line,
+        };
+
+        let size = match std::str::from_utf8(size) {
+            Ok(size) => size,
+            Err(_) => return Err(size_err),
+        };
+
+        if size.is_empty() || !size.chars().all(|x| x.is_ascii_hexdigit()) {
+            return Err(size_err);
+        }
+
+        usize::from_str_radix(size, 16).map_err(|_| size_err)
+    }
+}
+
+/// Check whether the chunk extensions of a chunk-size line, starting at the first `;`, follow
+/// `*( BWS ";" BWS token [ BWS "=" BWS ( token / quoted-string ) ] )`. Anything else, such as a
+/// bare LF, would be read differently by other parsers.
+fn is_chunk_ext(ext: &[u8]) -> bool {
+    let ext: String = ext.iter().map(|&x| x as char).collect();
+    let trim = |x: &str| x.trim_matches([' ', '\t']).to_string();
+
+    split_quoted(&ext, ';')
+        .iter()
+        .skip(1)
+        .all(|x| match x.split_once('=') {
+            Some((name, value)) => {
+                let value = trim(value);
+                is_token(&trim(name)) && (is_token(&value) || is_quoted_string(&value))
+            }
+            None => is_token(&trim(x)),
+        })
+}
+
+/// Decode a complete chunked body at the start of `buffer`. Returns the body, the trailer
+/// headers, and the number of bytes the chunked body took up.
+pub(crate) fn decode(
+    buffer: &[u8],
+    config: &ParserConfig,
+) -> Result<(Vec<u8>, HeaderMap, usize), Error> {
+    let incomplete_err = Error {
+        err_type: ErrorType::ParserError,
+        msg: "Incomplete chunked body".to_string(),
+    };
+
+    let mut decoder = ChunkedDecoder::with_config(config);
+    let consumed = decoder.feed(buffer)?;
+
+    if !decoder.is_done() {
+        return Err(incomplete_err);
+    }
+
+    let (body, trailers) = decoder.into_parts();
+
+    Ok((body, trailers, consumed))
+}
+
+/// Encode a single chunk of a chunked body. An empty chunk encodes to nothing, since a zero size
+/// chunk would end the body.
+pub(crate) fn encode_chunk(chunk: &[u8]) -> Vec<u8> {
+    if chunk.is_empty() {
+        return Vec::new();
+    }
+
+    let mut encoded = format!("{:x}\r\n", chunk.len()).into_bytes();
+    encoded.extend_from_slice(chunk);
+    encoded.extend_from_slice(b"\r\n");
+
+    encoded
+}
+
+/// Encode the last chunk of a chunked body, followed by the trailer section.
+pub(crate) fn encode_last_chunk(trailers: &HeaderMap) -> Vec<u8> {
+    let trailers = trailers
+        .iter()
+        .filter(|x| x.has_valid_name())
+        .fold(String::new(), |a, b| a + &b.to_string() + "\r\n");
+
+    message::encode_latin1(&format!("0\r\n{}\r\n", trailers))
+}
+
+/// Encode a body as a single chunk followed by the last chunk and the trailer section.
+pub(crate) fn encode(body: &[u8], trailers: &HeaderMap) -> Vec<u8> {
+    let mut encoded = encode_chunk(body);
+    encoded.extend(encode_last_chunk(trailers));
+
+    encoded
+}
+
+/// Read a line ending with CRLF, of at most `limit` bytes not counting the CRLF. Only the first
+/// `limit + 2` bytes are searched, so an overlong line is found without scanning the whole input.
+fn read_line(input: &[u8], limit: usize) -> Option<&[u8]> {
+    input[..input.len().min(limit + 2)]
+        .windows(2)
+        .position(|x| x == b"\r\n")
+        .map(|x| &input[..x])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChunkedDecoder;
+    use crate::ErrorType;
+
+    #[test]
+    fn decode_chunked_body() {
+        let mut decoder = ChunkedDecoder::new();
+        let input = b"5\r\nhello\r\n7;name=value\r\n, world\r\n0\r\n\r\n";
+        let consumed = decoder.feed(input).unwrap();
+
+        assert!(decoder.is_done());
+        assert_eq!(consumed, input.len());
+        assert_eq!(decoder.into_parts().0, b"hello, world");
+    }
+
+    #[test]
+    fn decode_chunked_body_incrementally() {
+        let mut decoder = ChunkedDecoder::new();
+        let input = b"5\r\nhello\r\n0\r\nExpires: never\r\n\r\n";
+
+        let mut offset = 0;
+        for end in 1..=input.len() {
+            offset += decoder.feed(&input[offset..end]).unwrap();
+        }
+
+        assert!(decoder.is_done());
+
+        let (body, trailers) = decoder.into_parts();
+        assert_eq!(body, b"hello");
+        assert_eq!(trailers.get("expires"), Some("never"));
+    }
+
+    #[test]
+    fn decode_invalid_chunk_size() {
+        let mut decoder = ChunkedDecoder::new();
+        let result = decoder.feed(b"zz\r\nhello\r\n0\r\n\r\n");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn decode_chunk_size_with_whitespace() {
+        assert!(ChunkedDecoder::new().feed(b" 5\r\nhello\r\n").is_err());
+        assert!(ChunkedDecoder::new().feed(b"5 \r\nhello\r\n").is_err());
+        assert!(ChunkedDecoder::new().feed(b"5 ;a=b\r\nhello\r\n").is_ok());
+    }
+
+    #[test]
+    fn decode_chunk_extensions() {
+        let input = b"1 ; a ; b = \"x;\\\"y\" ;c=d\r\nX\r\n0\r\n\r\n";
+        assert!(ChunkedDecoder::new().feed(input).is_ok());
+
+        assert!(ChunkedDecoder::new()
+            .feed(b"1;a\nb\r\nX\r\n0\r\n\r\n")
+            .is_err());
+        assert!(ChunkedDecoder::new().feed(b"1;a=\"b\r\nX\r\n").is_err());
+        assert!(ChunkedDecoder::new().feed(b"1;\r\nX\r\n").is_err());
+        assert!(ChunkedDecoder::new().feed(b"1;a=b c\r\nX\r\n").is_err());
+    }
+
+    #[test]
+    fn decode_chunked_body_over_limits() {
+        let mut input = vec![b'1'; 1024 * 1024];
+        let result = ChunkedDecoder::new().feed(&input).unwrap_err();
+        assert_eq!(result.err_type, ErrorType::ParserError);
+
+        input = b"0\r\n".to_vec();
+        input.extend(b"X: a\r\n".repeat(200_000));
+        let result = ChunkedDecoder::new().feed(&input).unwrap_err();
+        assert_eq!(result.err_type, ErrorType::TooManyHeaders);
+
+        input = b"0\r\nX: ".to_vec();
+        input.extend(vec![b'a'; 10 * 1024]);
+        let result = ChunkedDecoder::new().feed(&input).unwrap_err();
+        assert_eq!(result.err_type, ErrorType::HeaderTooLarge);
+
+        input = b"0\r\n".to_vec();
+        input.extend(format!("X: {}\r\n", "a".repeat(8000)).repeat(10).as_bytes());
+        let result = ChunkedDecoder::new().feed(&input).unwrap_err();
+        assert_eq!(result.err_type, ErrorType::HeadersTooLarge);
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::date::HttpDate;
+use crate::error::{Error, ErrorType};
+use crate::header::{split_quoted, Header};
+use crate::method::Method;
+use crate::request::Request;
+use crate::response::Response;
+use crate::status::Status;
+
+/// An entity tag, as sent in the `ETag` header, e.g. `"abc"` or `W/"abc"`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ETag {
+    weak: bool,
+    tag: String,
+}
+
+impl ETag {
+    /// Create a strong entity tag. The tag is the part between the quotes.
+    pub fn strong(tag: &str) -> Self {
+        ETag {
+            weak: false,
+            tag: tag.to_string(),
+        }
+    }
+
+    /// Create a weak entity tag. The tag is the part between the quotes.
+    pub fn weak(tag: &str) -> Self {
+        ETag {
+            weak: true,
+            tag: tag.to_string(),
+        }
+    }
+
+    /// Create a strong entity tag from a hash of the body, so it changes whenever the body does.
+    pub fn from_bytes(content: &[u8]) -> Self {
+        // 64 bit FNV-1a, which is small and good enough to tell versions of a body apart.
+        let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, &x| {
+            (hash ^ u64::from(x)).wrapping_mul(0x100000001b3)
+        });
+
+        Self::strong(&format!("{:x}-{:016x}", content.len(), hash))
+    }
+
+    /// Parse an entity tag, e.g. `"abc"` or `W/"abc"`.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let etag_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid entity tag".to_string(),
+        };
+
+        let value = value.trim();
+
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, value),
+        };
+
+        let tag = match quoted.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+            Some(tag) => tag,
+            None => return Err(etag_err),
+        };
+
+        // Any visible character except `"` is allowed, as is obs-text.
+        if !tag
+            .chars()
+            .all(|x| x == '!' || ('#'..='~').contains(&x) || x >= '\u{80}')
+        {
+            return Err(etag_err);
+        }
+
+        Ok(ETag {
+            weak,
+            tag: tag.to_string(),
+        })
+    }
+
+    /// Get the tag, without the quotes or weak indicator.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Whether the entity tag is weak.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Strong comparison, where both tags must be strong and equal.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison, where the tags must be equal whether or not they are weak.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.weak {
+            true => write!(f, "W/\"{}\"", self.tag),
+            false => write!(f, "\"{}\"", self.tag),
+        }
+    }
+}
+
+/// The outcome of evaluating the conditional headers of a request, see
+/// [crate::Request::preconditions].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Precondition {
+    /// The conditions hold, or there are none, so the request should be handled as usual.
+    Proceed,
+    /// The client's cached copy is current, respond with `304 Not Modified`.
+    NotModified,
+    /// A condition failed, respond with `412 Precondition Failed`.
+    Failed,
+}
+
+impl Precondition {
+    /// Turn the response that would have been sent into the one for this outcome. A
+    /// `304 Not Modified` response keeps the `Cache-Control`, `Content-Location`, `Date`, `ETag`,
+    /// `Expires` and `Vary` headers, and drops the body. A `412 Precondition Failed` response
+    /// keeps none of them. When the request should proceed, the response is returned as is.
+    pub fn apply(self, response: Response) -> Response {
+        let kept = [
+            "Cache-Control",
+            "Content-Location",
+            "Date",
+            "ETag",
+            "Expires",
+            "Vary",
+        ];
+
+        match self {
+            Precondition::Proceed => response,
+            Precondition::NotModified => response
+                .headers()
+                .iter()
+                .filter(|x| kept.iter().any(|y| x.name().eq_ignore_ascii_case(y)))
+                .fold(Response::empty().status(Status::NotModified), |a, b| {
+                    a.header(b.clone())
+                }),
+            Precondition::Failed => Response::empty()
+                .status(Status::PreconditionFailed)
+                .header(Header::new("Content-Length", "0")),
+        }
+    }
+}
+
+/// Evaluate the conditional headers of a request against the current validators of the
+/// resource, in the order given by RFC 9110. The resource is assumed to exist.
+pub(crate) fn evaluate(
+    request: &Request,
+    etag: Option<&ETag>,
+    last_modified: Option<SystemTime>,
+) -> Precondition {
+    let safe = request.method == Method::GET || request.method == Method::HEAD;
+    let headers = &request.headers;
+
+    let modified_since = |value: &str| match (last_modified, HttpDate::parse(value)) {
+        (Some(last_modified), Ok(date)) => Some(HttpDate::from(last_modified) > date),
+        _ => None,
+    };
+
+    if headers.contains("If-Match") {
+        let matches = any_match(&headers.get_all("If-Match"), etag, ETag::strong_eq);
+
+        if !matches {
+            return Precondition::Failed;
+        }
+    } else if let Some(value) = headers.get("If-Unmodified-Since") {
+        if modified_since(value) == Some(true) {
+            return Precondition::Failed;
+        }
+    }
+
+    if headers.contains("If-None-Match") {
+        let matches = any_match(&headers.get_all("If-None-Match"), etag, ETag::weak_eq);
+
+        return match (matches, safe) {
+            (true, true) => Precondition::NotModified,
+            (true, false) => Precondition::Failed,
+            (false, _) => Precondition::Proceed,
+        };
+    }
+
+    if let Some(value) = headers.get("If-Modified-Since") {
+        if safe && modified_since(value) == Some(false) {
+            return Precondition::NotModified;
+        }
+    }
+
+    Precondition::Proceed
+}
+
+/// Check whether the entity tag matches any in the values of an `If-Match` or `If-None-Match`
+/// header. A `*` matches any entity tag. Malformed entity tags are skipped.
+fn any_match(values: &[&str], etag: Option<&ETag>, eq: fn(&ETag, &ETag) -> bool) -> bool {
+    let etag = match etag {
+        Some(etag) => etag,
+        None => return values.iter().any(|x| x.trim() == "*"),
+    };
+
+    values
+        .iter()
+        .flat_map(|x| split_quoted(x, ','))
+        .any(|x| x.trim() == "*" || ETag::parse(&x).is_ok_and(|x| eq(&x, etag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::ETag;
+    use crate::Header;
+    use crate::Method;
+    use crate::Precondition;
+    use crate::Request;
+    use crate::Response;
+    use crate::Status;
+
+    fn request(method: Method, header: Header) -> Request {
+        Request::builder(method, "/")
+            .header(header)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_etag() {
+        let result = ETag::parse("W/\"abc\"").unwrap();
+
+        assert!(result.is_weak());
+        assert_eq!(result.tag(), "abc");
+        assert_eq!(result.to_string(), "W/\"abc\"");
+        assert!(ETag::parse("abc").is_err());
+        assert!(ETag::parse("\"a\"b\"").is_err());
+    }
+
+    #[test]
+    fn compare_etags() {
+        assert!(ETag::strong("1").strong_eq(&ETag::strong("1")));
+        assert!(!ETag::weak("1").strong_eq(&ETag::weak("1")));
+        assert!(ETag::weak("1").weak_eq(&ETag::strong("1")));
+        assert!(!ETag::weak("1").weak_eq(&ETag::weak("2")));
+    }
+
+    #[test]
+    fn etag_from_bytes() {
+        assert_eq!(ETag::from_bytes(b"hello"), ETag::from_bytes(b"hello"));
+        assert_ne!(ETag::from_bytes(b"hello"), ETag::from_bytes(b"hellp"));
+    }
+
+    #[test]
+    fn if_none_match() {
+        let etag = ETag::weak("v1");
+
+        let req = request(Method::GET, Header::new("If-None-Match", "\"v0\", \"v1\""));
+        assert_eq!(
+            req.preconditions(Some(&etag), None),
+            Precondition::NotModified
+        );
+
+        let req = request(Method::PUT, Header::new("If-None-Match", "*"));
+        assert_eq!(req.preconditions(Some(&etag), None), Precondition::Failed);
+
+        let req = request(Method::GET, Header::new("If-None-Match", "\"v2\""));
+        assert_eq!(req.preconditions(Some(&etag), None), Precondition::Proceed);
+    }
+
+    #[test]
+    fn if_match() {
+        let req = request(Method::PUT, Header::new("If-Match", "\"v1\""));
+
+        assert_eq!(
+            req.preconditions(Some(&ETag::strong("v1")), None),
+            Precondition::Proceed
+        );
+        assert_eq!(
+            req.preconditions(Some(&ETag::weak("v1")), None),
+            Precondition::Failed
+        );
+    }
+
+    #[test]
+    fn if_modified_since() {
+        let last_modified = UNIX_EPOCH + Duration::from_secs(784111777);
+        let req = request(
+            Method::GET,
+            Header::new("If-Modified-Since", "Sun, 06 Nov 1994 08:49:37 GMT"),
+        );
+
+        assert_eq!(
+            req.preconditions(None, Some(last_modified)),
+            Precondition::NotModified
+        );
+        assert_eq!(
+            req.preconditions(None, Some(last_modified + Duration::from_secs(1))),
+            Precondition::Proceed
+        );
+
+        let req = request(
+            Method::POST,
+            Header::new("If-Unmodified-Since", "Sun, 06 Nov 1994 08:49:36 GMT"),
+        );
+
+        assert_eq!(
+            req.preconditions(None, Some(last_modified)),
+            Precondition::Failed
+        );
+    }
+
+    #[test]
+    fn apply_precondition() {
+        let response = Response::html("<p>Hello</p>")
+            .etag(&ETag::strong("v1"))
+            .last_modified(UNIX_EPOCH)
+            .header(Header::new("Vary", "Accept"));
+        let result = Precondition::NotModified.apply(response);
+
+        assert_eq!(result.get_status(), &Status::NotModified);
+        assert_eq!(result.headers().get("ETag"), Some("\"v1\""));
+        assert_eq!(result.headers().get("Vary"), Some("Accept"));
+        assert_eq!(result.headers().get("Last-Modified"), None);
+        assert_eq!(result.headers().get("Content-Type"), None);
+        assert!(result.get_body().is_empty());
+    }
+}
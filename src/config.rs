@@ -0,0 +1,55 @@
+/// Options controlling how strictly incoming messages are parsed, and how large they can be. Both
+/// [ParserConfig::strict] and [ParserConfig::lenient] start with the same default limits.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParserConfig {
+    /// Reject malformed start lines, header lines and query pairs with an error. When disabled,
+    /// malformed header lines and query pairs are silently skipped instead, and folded header
+    /// lines, bare CRs and LFs, and other control characters are replaced with spaces. Invalid
+    /// header names and conflicting framing headers are rejected either way, since they can be
+    /// used to smuggle requests.
+    pub strict: bool,
+    /// The maximum length of the request line, in bytes.
+    pub max_request_line_len: usize,
+    /// The maximum length of the request target, in bytes.
+    pub max_uri_len: usize,
+    /// The maximum number of header lines. Trailers are limited separately by the same amount.
+    pub max_headers: usize,
+    /// The maximum length of a single header line, in bytes. This also limits trailer lines and
+    /// the chunk-size lines of a chunked body.
+    pub max_header_len: usize,
+    /// The maximum length of the whole header section, not counting the start line, in bytes.
+    /// Trailers are limited separately by the same amount.
+    pub max_head_len: usize,
+    /// The maximum length of the body, in bytes. For a chunked body this is the decoded length.
+    pub max_body_len: usize,
+}
+
+impl ParserConfig {
+    /// Create a config which rejects any malformed input. This is the default.
+    pub fn strict() -> Self {
+        ParserConfig {
+            strict: true,
+            max_request_line_len: 8 * 1024,
+            max_uri_len: 8 * 1024,
+            max_headers: 100,
+            max_header_len: 8 * 1024,
+            max_head_len: 64 * 1024,
+            max_body_len: 10 * 1024 * 1024,
+        }
+    }
+
+    /// Create a config which skips malformed header lines and query pairs, and accepts loosely
+    /// formatted start lines.
+    pub fn lenient() -> Self {
+        ParserConfig {
+            strict: false,
+            ..Self::strict()
+        }
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt;
+use std::time::UNIX_EPOCH;
+
+use crate::date::HttpDate;
+use crate::error::{Error, ErrorType};
+use crate::header::is_token;
+
+/// A cookie sent by the client in the `Cookie` header.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cookie {
+    name: String,
+    value: String,
+}
+
+impl Cookie {
+    /// Get the name of the cookie.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the value of the cookie, without any surrounding quotes.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Parse the value of a `Cookie` header, e.g. `session=abc; theme=dark`. Malformed pairs are
+    /// skipped.
+    pub(crate) fn parse_header(header: &str) -> Vec<Cookie> {
+        header
+            .split(';')
+            .flat_map(|x| x.trim().split_once('='))
+            .filter(|(name, value)| is_token(name) && is_cookie_value(value))
+            .map(|(name, value)| Cookie {
+                name: name.to_string(),
+                value: unquote(value).to_string(),
+            })
+            .collect()
+    }
+}
+
+/// The `SameSite` attribute of a cookie.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let same_site = match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        };
+
+        write!(f, "{}", same_site)
+    }
+}
+
+/// A cookie to send to the client in a `Set-Cookie` header, built up from a name and value with
+/// its attributes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    expires: Option<HttpDate>,
+    max_age: Option<i64>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool,
+}
+
+impl SetCookie {
+    /// Create a new cookie. Fails if the name is not a valid token, or the value contains
+    /// characters which aren't allowed in a cookie, such as spaces, commas or semicolons.
+    pub fn new(name: &str, value: &str) -> Result<Self, Error> {
+        let name_err = Error {
+            err_type: ErrorType::InvalidCookie,
+            msg: format!("Invalid cookie name '{}'", name),
+        };
+
+        let value_err = Error {
+            err_type: ErrorType::InvalidCookie,
+            msg: format!("Invalid cookie value '{}'", value),
+        };
+
+        if !is_token(name) {
+            return Err(name_err);
+        }
+
+        if !is_cookie_value(value) {
+            return Err(value_err);
+        }
+
+        Ok(SetCookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: None,
+            domain: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            partitioned: false,
+        })
+    }
+
+    /// Create a cookie which removes the cookie with the given name from the client. The path and
+    /// domain must match those of the cookie being removed.
+    pub fn removal(name: &str) -> Result<Self, Error> {
+        let cookie = Self::new(name, "")?
+            .expires(HttpDate::from(UNIX_EPOCH))
+            .max_age(0);
+
+        Ok(cookie)
+    }
+
+    /// Set the `Path` attribute.
+    pub fn path(self, path: &str) -> Result<Self, Error> {
+        Ok(SetCookie {
+            path: Some(attribute("Path", path)?),
+            ..self
+        })
+    }
+
+    /// Set the `Domain` attribute.
+    pub fn domain(self, domain: &str) -> Result<Self, Error> {
+        Ok(SetCookie {
+            domain: Some(attribute("Domain", domain)?),
+            ..self
+        })
+    }
+
+    /// Set the `Expires` attribute, from a `HttpDate` or a `SystemTime`.
+    pub fn expires(self, expires: impl Into<HttpDate>) -> Self {
+        SetCookie {
+            expires: Some(expires.into()),
+            ..self
+        }
+    }
+
+    /// Set the `Max-Age` attribute, in seconds. Zero or less expires the cookie immediately.
+    pub fn max_age(self, seconds: i64) -> Self {
+        SetCookie {
+            max_age: Some(seconds),
+            ..self
+        }
+    }
+
+    /// Set the `Secure` attribute.
+    pub fn secure(self) -> Self {
+        SetCookie {
+            secure: true,
+            ..self
+        }
+    }
+
+    /// Set the `HttpOnly` attribute.
+    pub fn http_only(self) -> Self {
+        SetCookie {
+            http_only: true,
+            ..self
+        }
+    }
+
+    /// Set the `SameSite` attribute. `SameSite::None` also sets the `Secure` attribute, since
+    /// browsers reject it otherwise.
+    pub fn same_site(self, same_site: SameSite) -> Self {
+        SetCookie {
+            secure: self.secure || same_site == SameSite::None,
+            same_site: Some(same_site),
+            ..self
+        }
+    }
+
+    /// Set the `Partitioned` attribute. This also sets the `Secure` attribute, since browsers
+    /// reject it otherwise.
+    pub fn partitioned(self) -> Self {
+        SetCookie {
+            secure: true,
+            partitioned: true,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for SetCookie {
+    /// Convert the `SetCookie` to the value of a `Set-Cookie` header.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+
+        if let Some(expires) = &self.expires {
+            write!(f, "; Expires={}", expires)?;
+        }
+
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age)?;
+        }
+
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+
+        if let Some(same_site) = &self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+
+        if self.partitioned {
+            write!(f, "; Partitioned")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Validate the value of a cookie attribute, which can't contain control characters or `;`.
+fn attribute(name: &str, value: &str) -> Result<String, Error> {
+    let attribute_err = Error {
+        err_type: ErrorType::InvalidCookie,
+        msg: format!("Invalid cookie {} attribute '{}'", name, value),
+    };
+
+    if value.is_empty() || value.chars().any(|x| x.is_control() || x == ';') {
+        return Err(attribute_err);
+    }
+
+    Ok(value.to_string())
+}
+
+/// Check whether a string is a valid cookie value, as defined in RFC 6265. The value may be
+/// wrapped in double quotes.
+fn is_cookie_value(value: &str) -> bool {
+    unquote(value)
+        .bytes()
+        .all(|x| matches!(x, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e))
+}
+
+fn unquote(value: &str) -> &str {
+    match value.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        Some(value) => value,
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cookie::Cookie;
+    use crate::{HttpDate, SameSite, SetCookie};
+
+    #[test]
+    fn parse_cookie_header() {
+        let result = Cookie::parse_header("session=abc123; theme=\"dark\";bad value=1; empty=");
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].name(), "session");
+        assert_eq!(result[0].value(), "abc123");
+        assert_eq!(result[1].value(), "dark");
+        assert_eq!(result[2].name(), "empty");
+        assert_eq!(result[2].value(), "");
+    }
+
+    #[test]
+    fn set_cookie_with_attributes() {
+        let result = SetCookie::new("id", "a3fWa")
+            .unwrap()
+            .domain("example.com")
+            .unwrap()
+            .expires(HttpDate::parse("Wed, 21 Oct 2015 07:28:00 GMT").unwrap())
+            .same_site(SameSite::None)
+            .partitioned()
+            .to_string();
+        let expected = "id=a3fWa; Domain=example.com; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Secure; SameSite=None; Partitioned";
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn set_cookie_validation() {
+        assert!(SetCookie::new("bad name", "value").is_err());
+        assert!(SetCookie::new("name", "a;b").is_err());
+        assert!(SetCookie::new("name", "a b").is_err());
+        assert!(SetCookie::new("name", "ok")
+            .unwrap()
+            .path("/\r\nX: y")
+            .is_err());
+    }
+
+    #[test]
+    fn removal_cookie() {
+        let result = SetCookie::removal("session").unwrap().to_string();
+        let expected = "session=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0";
+
+        assert_eq!(result, expected);
+    }
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, ErrorType};
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const LONG_DAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A HTTP-date, as used in the `Date`, `Last-Modified`, `Expires`, `If-Modified-Since` and
+/// `Retry-After` headers. Dates are accurate to the second, and can't be before the unix epoch.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct HttpDate {
+    secs: u64,
+}
+
+impl HttpDate {
+    /// Get the current date.
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+
+    /// Parse a HTTP-date in the preferred IMF-fixdate format, e.g.
+    /// `Sun, 06 Nov 1994 08:49:37 GMT`, or either of the obsolete RFC 850 and asctime formats,
+    /// e.g. `Sunday, 06-Nov-94 08:49:37 GMT` and `Sun Nov  6 08:49:37 1994`.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let date_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: format!("Invalid HTTP-date '{}'", value),
+        };
+
+        Self::parse_imf_fixdate(value)
+            .or_else(|| Self::parse_rfc850(value))
+            .or_else(|| Self::parse_asctime(value))
+            .ok_or(date_err)
+    }
+
+    fn parse_imf_fixdate(value: &str) -> Option<Self> {
+        let (day_name, rest) = value.split_once(", ")?;
+
+        if !DAYS.contains(&day_name) {
+            return None;
+        }
+
+        match rest.split(' ').collect::<Vec<_>>()[..] {
+            [day, month, year, time, "GMT"] if day.len() == 2 && year.len() == 4 => {
+                Self::from_parts(i64::from(number(year)?), month, day, time)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_rfc850(value: &str) -> Option<Self> {
+        let (day_name, rest) = value.split_once(", ")?;
+
+        if !LONG_DAYS.contains(&day_name) {
+            return None;
+        }
+
+        let (date, time) = match rest.split(' ').collect::<Vec<_>>()[..] {
+            [date, time, "GMT"] => (date, time),
+            _ => return None,
+        };
+
+        let (day, month, year) = match date.split('-').collect::<Vec<_>>()[..] {
+            [day, month, year] if day.len() == 2 && year.len() == 2 => (day, month, year),
+            _ => return None,
+        };
+
+        // A two digit year more than 50 years in the future is taken to be in the past.
+        let current_year = civil_from_days((Self::now().secs / 86400) as i64).0;
+        let mut year = current_year - current_year % 100 + i64::from(number(year)?);
+
+        if year > current_year + 50 {
+            year -= 100;
+        }
+
+        Self::from_parts(year, month, day, time)
+    }
+
+    fn parse_asctime(value: &str) -> Option<Self> {
+        if value.len() != 24 {
+            return None;
+        }
+
+        match value.split_whitespace().collect::<Vec<_>>()[..] {
+            [day_name, month, day, time, year] if DAYS.contains(&day_name) && year.len() == 4 => {
+                Self::from_parts(i64::from(number(year)?), month, day, time)
+            }
+            _ => None,
+        }
+    }
+
+    fn from_parts(year: i64, month: &str, day: &str, time: &str) -> Option<Self> {
+        let month = MONTHS.iter().position(|&x| x == month)? as u32 + 1;
+        let days = days_from_civil(year, month, number(day)?)?;
+
+        let secs = match time.split(':').collect::<Vec<_>>()[..] {
+            [hour, minute, second] if time.len() == 8 => {
+                let (hour, minute, second) = (number(hour)?, number(minute)?, number(second)?);
+
+                if hour > 23 || minute > 59 || second > 60 {
+                    return None;
+                }
+
+                u64::from(hour * 3600 + minute * 60 + second)
+            }
+            _ => return None,
+        };
+
+        Some(HttpDate {
+            secs: u64::try_from(days).ok()? * 86400 + secs,
+        })
+    }
+}
+
+impl From<SystemTime> for HttpDate {
+    /// Convert a `SystemTime` to a `HttpDate`. Times before the unix epoch are clamped to it, and
+    /// fractions of a second are dropped.
+    fn from(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+
+        HttpDate { secs }
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> Self {
+        UNIX_EPOCH + Duration::from_secs(date.secs)
+    }
+}
+
+impl fmt::Display for HttpDate {
+    /// Format the date as an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.secs / 86400;
+        let (year, month, day) = civil_from_days(days as i64);
+
+        write!(
+            f,
+            "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+            // The epoch was a Thursday.
+            DAYS[((days + 3) % 7) as usize],
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            self.secs % 86400 / 3600,
+            self.secs % 3600 / 60,
+            self.secs % 60
+        )
+    }
+}
+
+fn number(value: &str) -> Option<u32> {
+    if value.is_empty() || !value.chars().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+/// Convert a number of days since the unix epoch to a year, month and day.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Convert a year, month and day to a number of days since the unix epoch. Fails if the day
+/// doesn't exist.
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month_days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+
+    if day == 0 || day > month_days {
+        return None;
+    }
+
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::HttpDate;
+
+    #[test]
+    fn format_date() {
+        let result = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+
+        assert_eq!(result.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(
+            HttpDate::from(UNIX_EPOCH).to_string(),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        let expected = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+
+        assert_eq!(
+            HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap(),
+            expected
+        );
+        assert_eq!(
+            HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT").unwrap(),
+            expected
+        );
+        assert_eq!(
+            HttpDate::parse("Sun Nov  6 08:49:37 1994").unwrap(),
+            expected
+        );
+        assert_eq!(
+            HttpDate::parse("Tue, 29 Feb 2000 23:59:59 GMT")
+                .unwrap()
+                .to_string(),
+            "Tue, 29 Feb 2000 23:59:59 GMT"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_date() {
+        assert!(HttpDate::parse("Sun, 6 Nov 1994 08:49:37 GMT").is_err());
+        assert!(HttpDate::parse("Sun, 06 Nov 1994 08:49:37 UTC").is_err());
+        assert!(HttpDate::parse("Thu, 29 Feb 2001 00:00:00 GMT").is_err());
+        assert!(HttpDate::parse("Sun, 06 Nov 1994 24:00:00 GMT").is_err());
+        assert!(HttpDate::parse("Sun Nov 6 08:49:37 1994").is_err());
+        assert!(HttpDate::parse("Wed, 31 Dec 1969 23:59:59 GMT").is_err());
+    }
+
+    #[test]
+    fn convert_system_time() {
+        let time = UNIX_EPOCH + Duration::from_millis(784111777500);
+        let result = SystemTime::from(HttpDate::from(time));
+
+        assert_eq!(result, UNIX_EPOCH + Duration::from_secs(784111777));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::error::{Error, ErrorType};
+
+/// Decode a percent-encoded string into raw bytes. When `plus_as_space` is set, `+` is decoded
+/// as a space, as it is in query strings and form bodies.
+pub(crate) fn percent_decode_bytes(input: &str, plus_as_space: bool) -> Result<Vec<u8>, Error> {
+    let encoding_err = Error {
+        err_type: ErrorType::InvalidEncoding,
+        msg: format!("Invalid percent-encoding in '{}'", input),
+    };
+
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = match bytes.get(i + 1..i + 3) {
+                    Some(hex) => hex,
+                    None => return Err(encoding_err),
+                };
+
+                let high = (hex[0] as char).to_digit(16);
+                let low = (hex[1] as char).to_digit(16);
+
+                match (high, low) {
+                    (Some(high), Some(low)) => decoded.push((high * 16 + low) as u8),
+                    _ => return Err(encoding_err),
+                }
+
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// Decode a percent-encoded string. The decoded bytes must be valid UTF-8.
+pub(crate) fn percent_decode(input: &str, plus_as_space: bool) -> Result<String, Error> {
+    let utf8_err = Error {
+        err_type: ErrorType::InvalidEncoding,
+        msg: format!("Percent-encoded value '{}' is not valid UTF-8", input),
+    };
+
+    let decoded = percent_decode_bytes(input, plus_as_space)?;
+
+    String::from_utf8(decoded).map_err(|_| utf8_err)
+}
+
+/// Decode a single `key=value` pair from a query string or a `application/x-www-form-urlencoded`
+/// body. A pair without `=` has an empty value.
+pub(crate) fn decode_pair(pair: &str) -> Result<(String, String), Error> {
+    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+    Ok((percent_decode(key, true)?, percent_decode(value, true)?))
+}
+
+/// Percent-encode a string for use in a query string or form body. Everything except unreserved
+/// characters is encoded, including `+`, which would otherwise be decoded as a space.
+pub(crate) fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|x| match x {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (x as char).to_string()
+            }
+            _ => format!("%{:02X}", x),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{percent_decode, percent_encode};
+
+    #[test]
+    fn decode_percent_encoded_string() {
+        let result = percent_decode("hello%20world%21", false);
+
+        assert_eq!(result, Ok("hello world!".to_string()));
+    }
+
+    #[test]
+    fn decode_plus_as_space() {
+        assert_eq!(percent_decode("a+b", true), Ok("a b".to_string()));
+        assert_eq!(percent_decode("a+b", false), Ok("a+b".to_string()));
+    }
+
+    #[test]
+    fn decode_invalid_percent_encoding() {
+        assert!(percent_decode("100%", false).is_err());
+        assert!(percent_decode("%zz", false).is_err());
+        assert!(percent_decode("%ff", false).is_err());
+    }
+
+    #[test]
+    fn encode_reserved_characters() {
+        let result = percent_encode("a b+c&d=é");
+
+        assert_eq!(result, "a%20b%2Bc%26d%3D%C3%A9");
+        assert_eq!(percent_decode(&result, true), Ok("a b+c&d=é".to_string()));
+    }
+}
@@ -1,8 +1,44 @@
+use crate::status::Status;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ErrorType {
     ParserError,
     InvalidMethod,
+    UnsupportedVersion,
     InvalidCode,
+    InvalidEncoding,
+    InvalidCookie,
+    InvalidContentType,
+    ContentTooLarge,
+    InvalidFraming,
+    InvalidHeader,
+    InvalidHeaderName,
+    InvalidContentLength,
+    InvalidTransferEncoding,
+    ObsoleteLineFolding,
+    BareCarriageReturn,
+    ControlCharacter,
+    RequestLineTooLong,
+    UriTooLong,
+    TooManyHeaders,
+    HeaderTooLarge,
+    HeadersTooLarge,
+}
+
+impl ErrorType {
+    /// Get the status to respond with when a request fails to parse with this error.
+    pub fn status(&self) -> Status {
+        match self {
+            ErrorType::RequestLineTooLong | ErrorType::UriTooLong => Status::UriTooLong,
+            ErrorType::TooManyHeaders | ErrorType::HeaderTooLarge | ErrorType::HeadersTooLarge => {
+                Status::RequestHeaderFieldsTooLarge
+            }
+            ErrorType::ContentTooLarge => Status::ContentTooLarge,
+            ErrorType::InvalidContentType => Status::UnsupportedMediaType,
+            ErrorType::UnsupportedVersion => Status::HttpVersionNotSupported,
+            _ => Status::BadRequest,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -10,3 +46,14 @@ pub struct Error {
     pub err_type: ErrorType,
     pub msg: String,
 }
+
+impl Error {
+    /// Add the position of the input which failed to parse to the error message. Lines are
+    /// counted from 1, and the byte offset is from the start of the message.
+    pub(crate) fn at(self, line: usize, offset: usize) -> Self {
+        Error {
+            msg: format!("{} at line {}, byte {}", self.msg, line, offset),
+            ..self
+        }
+    }
+}
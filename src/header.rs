@@ -1,3 +1,9 @@
+use std::fmt;
+use std::ops::Index;
+use std::slice::Iter;
+
+use crate::error::{Error, ErrorType};
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Header {
     key: String,
@@ -5,25 +11,336 @@ pub struct Header {
 }
 
 impl Header {
-    /// Create a new http header from a key-value pair.
+    /// Create a new http header from a key-value pair. Characters which aren't allowed in the
+    /// value are replaced with spaces, so that a value such as `a\r\nSet-Cookie: b` can't inject
+    /// another header. A name which isn't a valid token is kept as given, but the header is
+    /// dropped by [crate::Response::header] and left out when a message is written. Use
+    /// [Header::try_new] to reject either instead.
     pub fn new(key: &str, value: &str) -> Self {
         Header {
             key: key.to_string(),
-            value: value.to_string(),
+            value: value
+                .chars()
+                .map(|x| if is_value_char(x) { x } else { ' ' })
+                .collect(),
+        }
+    }
+
+    /// Create a new http header from a key-value pair. Fails if the name is not a valid token, or
+    /// the value contains anything but visible characters, spaces and tabs.
+    pub fn try_new(key: &str, value: &str) -> Result<Self, Error> {
+        let name_err = Error {
+            err_type: ErrorType::InvalidHeader,
+            msg: format!("Invalid header name '{}'", key.escape_debug()),
+        };
+
+        let value_err = Error {
+            err_type: ErrorType::InvalidHeader,
+            msg: format!("Invalid value for header '{}'", key.escape_debug()),
+        };
+
+        if !is_token(key) {
+            return Err(name_err);
         }
+
+        if !value.chars().all(is_value_char) {
+            return Err(value_err);
+        }
+
+        Ok(Self::new(key, value))
+    }
+
+    /// Parse a single header line, e.g. `Content-Type: text/html`. The name must be a valid
+    /// token, with no whitespace before the colon, and the value can't contain control
+    /// characters. Whitespace around the value is trimmed.
+    pub(crate) fn parse(line: &str) -> Result<Self, Error> {
+        let parser_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid header format".to_string(),
+        };
+
+        let whitespace_err = Error {
+            err_type: ErrorType::InvalidHeaderName,
+            msg: "Whitespace between header name and colon".to_string(),
+        };
+
+        let name_err = Error {
+            err_type: ErrorType::InvalidHeaderName,
+            msg: "Invalid header name".to_string(),
+        };
+
+        let value_err = Error {
+            err_type: ErrorType::InvalidHeader,
+            msg: "Invalid header value".to_string(),
+        };
+
+        let (key, value) = match line.split_once(':') {
+            Some(parts) => parts,
+            None => return Err(parser_err),
+        };
+
+        if key.ends_with([' ', '\t']) {
+            return Err(whitespace_err);
+        }
+
+        if !is_token(key) {
+            return Err(name_err);
+        }
+
+        if !value.chars().all(is_value_char) {
+            return Err(value_err);
+        }
+
+        Ok(Self::new(
+            key,
+            value.trim_matches(|x| x == ' ' || x == '\t'),
+        ))
+    }
+
+    /// Whether the name of the header is a valid token, so it can be written out.
+    pub(crate) fn has_valid_name(&self) -> bool {
+        is_token(&self.key)
+    }
+
+    /// Get the name of the header, as it was given.
+    pub fn name(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the value of the header.
+    pub fn value(&self) -> &str {
+        &self.value
     }
 }
 
-impl ToString for Header {
+impl fmt::Display for Header {
     /// Convert the `Header` to a valid http plaintext representation.
-    fn to_string(&self) -> String {
-        format!("{}: {}", self.key, self.value)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.value)
+    }
+}
+
+/// Check whether a string is a valid http token, as used for header names and methods.
+pub(crate) fn is_token(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(is_token_char)
+}
+
+fn is_token_char(x: char) -> bool {
+    x.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(x)
+}
+
+/// Check whether a character is allowed in a header value, i.e. visible characters, obs-text,
+/// spaces and tabs.
+pub(crate) fn is_value_char(x: char) -> bool {
+    x == ' ' || x == '\t' || x.is_ascii_graphic() || x >= '\u{80}'
+}
+
+/// Split a header value on `separator`, ignoring separators inside quoted strings.
+pub(crate) fn split_quoted(value: &str, separator: char) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for x in value.chars() {
+        match x {
+            _ if escaped => {
+                segment.push(x);
+                escaped = false;
+            }
+            '\\' if quoted => {
+                segment.push(x);
+                escaped = true;
+            }
+            '"' => {
+                segment.push(x);
+                quoted = !quoted;
+            }
+            _ if x == separator && !quoted => segments.push(std::mem::take(&mut segment)),
+            _ => segment.push(x),
+        }
+    }
+
+    segments.push(segment);
+
+    segments
+}
+
+/// Split a header value with parameters, e.g. `form-data; name="file"`, into the main value and
+/// its parameters. Parameter names are lowercased, and quoted parameter values are unquoted.
+pub(crate) fn parse_parameters(value: &str) -> (String, Vec<(String, String)>) {
+    let mut segments = split_quoted(value, ';').into_iter();
+    let main = segments.next().unwrap_or_default().trim().to_string();
+
+    let parameters = segments
+        .flat_map(|x| {
+            x.split_once('=')
+                .map(|(name, value)| (name.trim().to_ascii_lowercase(), unquote(value.trim())))
+        })
+        .collect();
+
+    (main, parameters)
+}
+
+/// Remove the quotes around a quoted string, along with any backslash escapes.
+/// Check whether a value is a single valid quoted-string, e.g. `"a \"b\""`.
+pub(crate) fn is_quoted_string(value: &str) -> bool {
+    let inner = match value.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return false,
+    };
+
+    let mut escaped = false;
+
+    for x in inner.chars() {
+        match x {
+            _ if !is_value_char(x) => return false,
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return false,
+            _ => (),
+        }
+    }
+
+    !escaped
+}
+
+fn unquote(value: &str) -> String {
+    let inner = match value.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return value.to_string(),
+    };
+
+    let mut unquoted = String::new();
+    let mut escaped = false;
+
+    for x in inner.chars() {
+        match x {
+            '\\' if !escaped => escaped = true,
+            _ => {
+                unquoted.push(x);
+                escaped = false;
+            }
+        }
+    }
+
+    unquoted
+}
+
+/// An ordered collection of http headers. Lookups by name are case-insensitive, and a name can
+/// have more than one value, e.g. `Set-Cookie`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct HeaderMap {
+    headers: Vec<Header>,
+}
+
+impl HeaderMap {
+    /// Create a new empty header map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value of the first header with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|x| x.key.eq_ignore_ascii_case(name))
+            .map(|x| x.value.as_str())
+    }
+
+    /// Get the values of every header with the given name, in order.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|x| x.key.eq_ignore_ascii_case(name))
+            .map(|x| x.value.as_str())
+            .collect()
+    }
+
+    /// Check whether there is a header with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|x| x.key.eq_ignore_ascii_case(name))
+    }
+
+    /// Add a header, replacing any existing headers with the same name. The header takes the
+    /// position of the first header it replaces.
+    pub fn insert(&mut self, header: Header) {
+        match self
+            .headers
+            .iter()
+            .position(|x| x.key.eq_ignore_ascii_case(&header.key))
+        {
+            Some(index) => {
+                self.remove(&header.key);
+                self.headers.insert(index, header);
+            }
+            None => self.headers.push(header),
+        }
+    }
+
+    /// Add a header, keeping any existing headers with the same name.
+    pub fn append(&mut self, header: Header) {
+        self.headers.push(header);
+    }
+
+    /// Remove every header with the given name. Returns whether any headers were removed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.headers.len();
+        self.headers.retain(|x| !x.key.eq_ignore_ascii_case(name));
+
+        self.headers.len() != len
+    }
+
+    /// Iterate over the headers in insertion order.
+    pub fn iter(&self) -> Iter<'_, Header> {
+        self.headers.iter()
+    }
+
+    /// Get the number of headers, counting each value separately.
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Check whether there are no headers.
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+}
+
+impl Index<usize> for HeaderMap {
+    type Output = Header;
+
+    /// Get the header at the given position, in insertion order.
+    fn index(&self, index: usize) -> &Header {
+        &self.headers[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = &'a Header;
+    type IntoIter = Iter<'a, Header>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.headers.iter()
+    }
+}
+
+impl FromIterator<Header> for HeaderMap {
+    fn from_iter<T: IntoIterator<Item = Header>>(iter: T) -> Self {
+        HeaderMap {
+            headers: iter.into_iter().collect(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::header::parse_parameters;
+    use crate::ErrorType;
     use crate::Header;
+    use crate::HeaderMap;
 
     #[test]
     fn create_new_header() {
@@ -36,6 +353,41 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn create_header_with_invalid_characters() {
+        let result = Header::new("Location", "/a\r\nSet-Cookie: b\0");
+
+        assert_eq!(result.to_string(), "Location: /a  Set-Cookie: b ");
+    }
+
+    #[test]
+    fn create_header_with_invalid_name() {
+        let result = Header::new("Loca\r\ntion", "/a");
+
+        assert_eq!(result.name(), "Loca\r\ntion");
+        assert!(!result.has_valid_name());
+    }
+
+    #[test]
+    fn try_create_header() {
+        assert!(Header::try_new("Location", "/a\tb c").is_ok());
+
+        let result = Header::try_new("Location", "/a\r\nSet-Cookie: b").unwrap_err();
+        assert_eq!(result.err_type, ErrorType::InvalidHeader);
+
+        let result = Header::try_new("Bad Name", "x").unwrap_err();
+        assert_eq!(result.err_type, ErrorType::InvalidHeader);
+        assert!(Header::try_new("", "x").is_err());
+    }
+
+    #[test]
+    fn parse_header_with_invalid_value() {
+        assert_eq!(Header::parse("X:  a\tb ").unwrap().value(), "a\tb");
+
+        let result = Header::parse("X: a\0b").unwrap_err();
+        assert_eq!(result.err_type, ErrorType::InvalidHeader);
+    }
+
     #[test]
     fn string_representation() {
         let result = Header::new("Content-Type", "text/html").to_string();
@@ -43,4 +395,54 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn parse_header_parameters() {
+        let (result, parameters) =
+            parse_parameters("form-data; Name=\"file\"; filename=\"a;\\\"b\\\".txt\"");
+
+        assert_eq!(result, "form-data");
+        assert_eq!(parameters[0], ("name".to_string(), "file".to_string()));
+        assert_eq!(
+            parameters[1],
+            ("filename".to_string(), "a;\"b\".txt".to_string())
+        );
+    }
+
+    #[test]
+    fn header_map_case_insensitive_lookup() {
+        let mut headers = HeaderMap::new();
+        headers.append(Header::new("Content-Type", "text/html"));
+        headers.append(Header::new("Set-Cookie", "a=1"));
+        headers.append(Header::new("set-cookie", "b=2"));
+
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.get_all("SET-COOKIE"), vec!["a=1", "b=2"]);
+        assert!(headers.contains("Set-Cookie"));
+        assert!(!headers.contains("Location"));
+    }
+
+    #[test]
+    fn header_map_insert_replaces_in_place() {
+        let mut headers = HeaderMap::new();
+        headers.append(Header::new("Accept", "*/*"));
+        headers.append(Header::new("Cache-Control", "no-cache"));
+        headers.append(Header::new("Host", "localhost"));
+        headers.append(Header::new("cache-control", "no-store"));
+        headers.insert(Header::new("Cache-Control", "max-age=60"));
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers[1], Header::new("Cache-Control", "max-age=60"));
+        assert_eq!(headers[2], Header::new("Host", "localhost"));
+    }
+
+    #[test]
+    fn header_map_remove() {
+        let mut headers = HeaderMap::new();
+        headers.append(Header::new("Accept", "*/*"));
+
+        assert!(headers.remove("accept"));
+        assert!(!headers.remove("accept"));
+        assert!(headers.is_empty());
+    }
 }
@@ -49,14 +49,12 @@
 //! Use `nanohttp` to build a custom TCP server using only the
 //! [async-std](https://docs.rs/async-std/latest/async_std/) crate as a dependency.
 //!
-//! ```
-//! use std::str::from_utf8;
-//!
+//! ```no_run
 //! use async_std::io::{ReadExt, WriteExt};
 //! use async_std::net::{TcpListener, TcpStream};
 //! use async_std::task;
 //!
-//! use nanohttp::{Method, Status, Request, Response};
+//! use nanohttp::{Method, ParseStatus, Request, RequestParser, Response, Status};
 //!
 //! async fn handler(req: Request) -> Response {
 //!     match req.path.uri.as_str() {
@@ -76,18 +74,29 @@
 //! }
 //!
 //! async fn handle_connection(mut connection: TcpStream) {
+//!     let mut parser = RequestParser::new();
 //!     let mut buffer = [0; 1024];
 //!
-//!     connection.read(&mut buffer).await.unwrap();
-//!
-//!     let req_text = from_utf8(&buffer).unwrap().trim_end_matches("\0");
-//!
-//!     let req = Request::from_string(req_text).unwrap();
-//!     let res = handler(req).await.to_string();
-//!
-//!     let res_bytes = res.as_bytes();
-//!
-//!     connection.write(res_bytes).await.unwrap();
+//!     let req = loop {
+//!         let len = connection.read(&mut buffer).await.unwrap();
+//!
+//!         if len == 0 {
+//!             return;
+//!         }
+//!
+//!         match parser.feed(&buffer[..len]) {
+//!             Ok(ParseStatus::Complete { request, .. }) => break request,
+//!             Ok(ParseStatus::Partial) => (),
+//!             Err(err) => {
+//!                 let res = Response::empty().status(err.err_type.status()).to_bytes();
+//!                 connection.write_all(&res).await.unwrap();
+//!                 return;
+//!             }
+//!         }
+//!     };
+//!     let res = handler(req).await.to_bytes();
+//!
+//!     connection.write_all(&res).await.unwrap();
 //!     connection.flush().await.unwrap();
 //! }
 //!
@@ -104,16 +113,36 @@
 //! }
 //! ```
 
+mod chunked;
+mod conditional;
+mod config;
+mod cookie;
+mod date;
+mod encoding;
 mod error;
 mod header;
+mod message;
 mod method;
+mod multipart;
+mod negotiate;
+mod range;
 mod request;
 mod response;
 mod status;
+mod uri;
 
+pub use chunked::ChunkedDecoder;
+pub use conditional::{ETag, Precondition};
+pub use config::ParserConfig;
+pub use cookie::{Cookie, SameSite, SetCookie};
+pub use date::HttpDate;
 pub use error::{Error, ErrorType};
-pub use header::Header;
+pub use header::{Header, HeaderMap};
 pub use method::Method;
-pub use request::{Path, Request};
+pub use multipart::{MultipartLimits, MultipartReader, Part};
+pub use negotiate::{Negotiation, Preference};
+pub use range::ByteRange;
+pub use request::{ParseStatus, Path, Query, Request, RequestBuilder, RequestParser};
 pub use response::Response;
 pub use status::Status;
+pub use uri::{TargetForm, Uri};
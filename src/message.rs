@@ -0,0 +1,355 @@
+use crate::chunked;
+use crate::config::ParserConfig;
+use crate::error::{Error, ErrorType};
+use crate::header::{Header, HeaderMap};
+
+/// How the length of a message body is determined.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Framing {
+    Length(usize),
+    Chunked,
+}
+
+/// Find the end of the header section, i.e. the index just after the blank line.
+pub(crate) fn find_head_end(buffer: &[u8]) -> Option<usize> {
+    buffer
+        .windows(4)
+        .position(|x| x == b"\r\n\r\n")
+        .map(|x| x + 4)
+}
+
+/// Decode the head of a message. Bytes outside of ASCII are mapped one-to-one onto chars, i.e.
+/// latin-1.
+pub(crate) fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&x| x as char).collect()
+}
+
+/// Get the length in bytes of the message that part of a decoded head came from. Each byte is
+/// decoded to a single char, which can take up more than one byte in a `String`.
+pub(crate) fn byte_len(value: &str) -> usize {
+    value.chars().count()
+}
+
+/// Encode the head of a message, the reverse of [decode_latin1]. Characters outside of latin-1
+/// can't be written as a single byte, so they are written as UTF-8 instead.
+pub(crate) fn encode_latin1(head: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(head.len());
+
+    for x in head.chars() {
+        match u8::try_from(x) {
+            Ok(byte) => bytes.push(byte),
+            Err(_) => bytes.extend_from_slice(x.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+
+    bytes
+}
+
+/// Deal with any bare CR or LF, i.e. one which isn't part of a CRLF, and any other control
+/// character except HTAB in the head of a message. Strict parsing rejects them, while lenient
+/// parsing replaces each one with a space.
+pub(crate) fn replace_control_chars(head: String, config: &ParserConfig) -> Result<String, Error> {
+    let bare_cr_err = Error {
+        err_type: ErrorType::BareCarriageReturn,
+        msg: "Bare CR in message head".to_string(),
+    };
+
+    let control_err = Error {
+        err_type: ErrorType::ControlCharacter,
+        msg: "Control character in message head".to_string(),
+    };
+
+    let bytes = head.as_bytes();
+
+    let invalid = |x: usize| match bytes[x] {
+        b'\r' => bytes.get(x + 1) != Some(&b'\n'),
+        b'\n' => x == 0 || bytes[x - 1] != b'\r',
+        b'\t' => false,
+        byte => byte < 0x20 || byte == 0x7f,
+    };
+
+    match (0..bytes.len()).find(|&x| invalid(x)) {
+        None => Ok(head),
+        Some(index) if config.strict => {
+            let line = head[..index].matches("\r\n").count() + 1;
+            let offset = byte_len(&head[..index]);
+
+            match bytes[index] {
+                b'\r' => Err(bare_cr_err.at(line, offset)),
+                _ => Err(control_err.at(line, offset)),
+            }
+        }
+        Some(_) => Ok(head
+            .char_indices()
+            .map(|(index, x)| if invalid(index) { ' ' } else { x })
+            .collect()),
+    }
+}
+
+/// Parse the header lines following the start line, up to the blank line. `offset` is the
+/// position of the first header line in the message, and is used to report the position of
+/// malformed lines.
+///
+/// Lines folded onto the previous line (obs-fold) are rejected in strict mode, and joined with a
+/// space in lenient mode. Lenient mode also skips lines which aren't headers at all, but an
+/// invalid header name is always an error.
+pub(crate) fn parse_headers<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    offset: usize,
+    config: &ParserConfig,
+) -> Result<HeaderMap, Error> {
+    let fold_err = Error {
+        err_type: ErrorType::ObsoleteLineFolding,
+        msg: "Obsolete line folding in header".to_string(),
+    };
+
+    let count_err = Error {
+        err_type: ErrorType::TooManyHeaders,
+        msg: format!("More than {} headers", config.max_headers),
+    };
+
+    let line_err = Error {
+        err_type: ErrorType::HeaderTooLarge,
+        msg: format!("Header longer than {} bytes", config.max_header_len),
+    };
+
+    let head_err = Error {
+        err_type: ErrorType::HeadersTooLarge,
+        msg: format!("Headers longer than {} bytes", config.max_head_len),
+    };
+
+    let mut headers: Vec<Header> = Vec::new();
+    let start = offset;
+    let mut offset = offset;
+
+    for (index, line) in lines.take_while(|x| !x.is_empty()).enumerate() {
+        if index >= config.max_headers {
+            return Err(count_err.at(index + 2, offset));
+        }
+
+        let len = byte_len(line);
+
+        if len > config.max_header_len {
+            return Err(line_err.at(index + 2, offset));
+        }
+
+        if offset - start + len + 2 > config.max_head_len {
+            return Err(head_err.at(index + 2, offset));
+        }
+
+        let folded = line.starts_with([' ', '\t']);
+
+        match headers.last_mut() {
+            Some(_) if folded && config.strict => return Err(fold_err.at(index + 2, offset)),
+            Some(last) if folded => {
+                let value = format!("{} {}", last.value(), line.trim_matches([' ', '\t']));
+                *last = Header::new(last.name(), &value);
+            }
+            _ => match Header::parse(line) {
+                Ok(header) => headers.push(header),
+                Err(err) if err.err_type == ErrorType::ParserError && !config.strict => (),
+                Err(err) => return Err(err.at(index + 2, offset)),
+            },
+        }
+
+        offset += len + 2;
+    }
+
+    Ok(headers.into_iter().collect())
+}
+
+/// Check the framing headers of a message for combinations RFC 9112 says must be handled as
+/// errors, i.e. both `Transfer-Encoding` and `Content-Length`, or conflicting `Content-Length`
+/// values. Identical duplicate `Content-Length` values are collapsed into a single header.
+pub(crate) fn check_framing(headers: &mut HeaderMap) -> Result<(), Error> {
+    let conflict_err = Error {
+        err_type: ErrorType::InvalidFraming,
+        msg: "Message has both Transfer-Encoding and Content-Length headers".to_string(),
+    };
+
+    let length_err = Error {
+        err_type: ErrorType::InvalidContentLength,
+        msg: "Invalid or conflicting Content-Length headers".to_string(),
+    };
+
+    let lengths: Vec<&str> = headers
+        .get_all("Content-Length")
+        .into_iter()
+        .flat_map(|x| x.split(','))
+        .map(|x| x.trim())
+        .collect();
+
+    let length = match lengths.first() {
+        Some(_) if headers.contains("Transfer-Encoding") => return Err(conflict_err),
+        Some(&length) => length.to_string(),
+        None => return Ok(()),
+    };
+
+    if !lengths.iter().all(|&x| x == length && is_length(x)) {
+        return Err(length_err);
+    }
+
+    headers.insert(Header::new("Content-Length", &length));
+
+    Ok(())
+}
+
+/// Check the framing headers of a request for the combinations which can be used to smuggle
+/// requests, as set out in RFC 9112. On top of [check_framing], a `Transfer-Encoding` must end
+/// with chunked, since a request body can't be delimited by closing the connection.
+pub(crate) fn check_request_framing(headers: &mut HeaderMap) -> Result<(), Error> {
+    let encoding_err = Error {
+        err_type: ErrorType::InvalidTransferEncoding,
+        msg: "Transfer-Encoding must end with chunked, exactly once".to_string(),
+    };
+
+    check_framing(headers)?;
+
+    if !headers.contains("Transfer-Encoding") {
+        return Ok(());
+    }
+
+    let codings: Vec<String> = headers
+        .get_all("Transfer-Encoding")
+        .into_iter()
+        .flat_map(|x| x.split(','))
+        .map(|x| x.trim().to_ascii_lowercase())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    let chunked = codings.iter().filter(|x| *x == "chunked").count();
+
+    if chunked != 1 || codings.last().map(|x| x.as_str()) != Some("chunked") {
+        return Err(encoding_err);
+    }
+
+    Ok(())
+}
+
+/// Work out how the body is framed. `Transfer-Encoding: chunked` takes priority, otherwise the
+/// length comes from the `Content-Length` header. Returns `None` if neither header is set.
+pub(crate) fn framing(headers: &HeaderMap) -> Result<Option<Framing>, Error> {
+    let parser_err = Error {
+        err_type: ErrorType::ParserError,
+        msg: "Invalid Content-Length header".to_string(),
+    };
+
+    let length_err = Error {
+        err_type: ErrorType::ContentTooLarge,
+        msg: "Content-Length header is too large".to_string(),
+    };
+
+    let chunked = headers
+        .get_all("Transfer-Encoding")
+        .into_iter()
+        .flat_map(|x| x.split(','))
+        .last()
+        .is_some_and(|x| x.trim().eq_ignore_ascii_case("chunked"));
+
+    if chunked {
+        return Ok(Some(Framing::Chunked));
+    }
+
+    // A valid length which doesn't fit in a usize is certainly over any body limit.
+    match headers.get("Content-Length").map(|x| x.trim()) {
+        Some(value) if is_length(value) => match value.parse() {
+            Ok(len) => Ok(Some(Framing::Length(len))),
+            Err(_) => Err(length_err),
+        },
+        Some(_) => Err(parser_err),
+        None => Ok(None),
+    }
+}
+
+/// Check the length of a body against the limit in the config.
+pub(crate) fn check_body_len(len: usize, config: &ParserConfig) -> Result<(), Error> {
+    let body_err = Error {
+        err_type: ErrorType::ContentTooLarge,
+        msg: format!("Body longer than {} bytes", config.max_body_len),
+    };
+
+    if len > config.max_body_len {
+        return Err(body_err);
+    }
+
+    Ok(())
+}
+
+/// Read a complete body from the bytes following the head of a message. Returns the body, any
+/// trailers, and the number of bytes the body took up.
+pub(crate) fn read_body(
+    buffer: &[u8],
+    framing: Framing,
+    config: &ParserConfig,
+) -> Result<(Vec<u8>, HeaderMap, usize), Error> {
+    let body_err = Error {
+        err_type: ErrorType::ParserError,
+        msg: "Body is shorter than Content-Length".to_string(),
+    };
+
+    match framing {
+        Framing::Length(len) if buffer.len() < len => Err(body_err),
+        Framing::Length(len) => Ok((buffer[..len].to_vec(), HeaderMap::new(), len)),
+        Framing::Chunked => chunked::decode(buffer, config),
+    }
+}
+
+/// Parse the protocol of a start line, e.g. `HTTP/1.1`, into its scheme and version.
+pub(crate) fn parse_protocol<'a>(
+    line: &'a str,
+    config: &ParserConfig,
+) -> Result<(&'a str, &'a str), Error> {
+    let parser_err = Error {
+        err_type: ErrorType::ParserError,
+        msg: "Invalid protocol format".to_string(),
+    };
+
+    let version_err = Error {
+        err_type: ErrorType::UnsupportedVersion,
+        msg: format!("Unsupported protocol version '{}'", line),
+    };
+
+    let mut parts = line.split("/");
+
+    let scheme = match parts.next() {
+        Some(scheme) => scheme,
+        None => return Err(parser_err),
+    };
+
+    let version = match parts.next() {
+        Some(version) => version,
+        None => return Err(parser_err),
+    };
+
+    if config.strict {
+        let mut digits = version.split('.');
+
+        let valid = scheme == "HTTP"
+            && parts.next().is_none()
+            && digits.next().is_some_and(is_digit)
+            && digits.next().is_some_and(is_digit)
+            && digits.next().is_none();
+
+        if !valid {
+            return Err(parser_err);
+        }
+
+        // Only HTTP/1.x messages can be parsed, other major versions have a different syntax.
+        if !version.starts_with("1.") {
+            return Err(version_err);
+        }
+    }
+
+    Ok((scheme, version))
+}
+
+/// Check whether a `Content-Length` value is made of digits only. `usize::from_str` would also
+/// accept a leading `+`.
+pub(crate) fn is_length(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|x| x.is_ascii_digit())
+}
+
+/// Check whether a string is a single ascii digit, as in a http version.
+fn is_digit(value: &str) -> bool {
+    value.len() == 1 && value.chars().all(|x| x.is_ascii_digit())
+}
@@ -1,4 +1,7 @@
+use std::fmt;
+
 use crate::error::{Error, ErrorType};
+use crate::header::is_token;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Method {
@@ -8,6 +11,12 @@ pub enum Method {
     PUT,
     DELETE,
     PATCH,
+    OPTIONS,
+    CONNECT,
+    TRACE,
+    /// Any other method, e.g. WebDAV's `PROPFIND`. Methods are case-sensitive, so this holds the
+    /// method exactly as it was given.
+    Extension(String),
 }
 
 impl Method {
@@ -15,7 +24,7 @@ impl Method {
     pub fn from_string(from: &str) -> Result<Self, Error> {
         let method_err = Error {
             err_type: ErrorType::InvalidMethod,
-            msg: "Invalid or unsupported http method".to_string(),
+            msg: "Invalid http method".to_string(),
         };
 
         match from {
@@ -25,23 +34,48 @@ impl Method {
             "PUT" => Ok(Method::PUT),
             "DELETE" => Ok(Method::DELETE),
             "PATCH" => Ok(Method::PATCH),
+            "OPTIONS" => Ok(Method::OPTIONS),
+            "CONNECT" => Ok(Method::CONNECT),
+            "TRACE" => Ok(Method::TRACE),
+            method if is_token(method) => Ok(Method::Extension(method.to_string())),
             _ => Err(method_err),
         }
     }
+
+    /// Whether the method is safe, i.e. read-only, as defined in RFC 9110.
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Self::GET | Self::HEAD | Self::OPTIONS | Self::TRACE)
+    }
+
+    /// Whether repeating a request with this method has the same effect as sending it once, as
+    /// defined in RFC 9110.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Self::PUT | Self::DELETE)
+    }
+
+    /// Whether responses to this method are allowed to be cached, as defined in RFC 9110.
+    pub fn is_cacheable(&self) -> bool {
+        matches!(self, Self::GET | Self::HEAD | Self::POST)
+    }
 }
 
-impl ToString for Method {
+impl fmt::Display for Method {
     // Get the string representation of a `Method`.
-    fn to_string(&self) -> String {
-        match self {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let method = match self {
             Self::HEAD => "HEAD",
             Self::GET => "GET",
             Self::POST => "POST",
             Self::PUT => "PUT",
             Self::DELETE => "DELETE",
             Self::PATCH => "PATCH",
-        }
-        .to_string()
+            Self::OPTIONS => "OPTIONS",
+            Self::CONNECT => "CONNECT",
+            Self::TRACE => "TRACE",
+            Self::Extension(method) => method,
+        };
+
+        write!(f, "{}", method)
     }
 }
 
@@ -59,8 +93,27 @@ mod tests {
 
     #[test]
     fn method_from_invalid_string() {
-        let result = Method::from_string("HELLO");
+        let result = Method::from_string("HEL(LO)");
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn extension_method_from_string() {
+        let result = Method::from_string("PROPFIND");
+        let expected = Ok(Method::Extension("PROPFIND".to_string()));
+
+        assert_eq!(result, expected);
+        assert_eq!(result.unwrap().to_string(), "PROPFIND");
+    }
+
+    #[test]
+    fn method_classification() {
+        assert!(Method::OPTIONS.is_safe());
+        assert!(!Method::POST.is_safe());
+        assert!(Method::DELETE.is_idempotent());
+        assert!(!Method::PATCH.is_idempotent());
+        assert!(Method::HEAD.is_cacheable());
+        assert!(!Method::Extension("PROPFIND".to_string()).is_idempotent());
+    }
 }
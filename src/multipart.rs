@@ -0,0 +1,505 @@
+use std::io::Read;
+
+use crate::error::{Error, ErrorType};
+use crate::header::{parse_parameters, Header, HeaderMap};
+use crate::message;
+
+/// Limits enforced while reading a multipart body.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MultipartLimits {
+    /// The maximum number of parts.
+    pub max_parts: usize,
+    /// The maximum size of the body of a single part, in bytes.
+    pub max_part_size: usize,
+    /// The maximum size of the header section of a single part, in bytes.
+    pub max_header_size: usize,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        MultipartLimits {
+            max_parts: 100,
+            max_part_size: 10 * 1024 * 1024,
+            max_header_size: 8 * 1024,
+        }
+    }
+}
+
+/// The headers of a single part of a multipart body.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Part {
+    headers: HeaderMap,
+    name: Option<String>,
+    filename: Option<String>,
+}
+
+impl Part {
+    /// Get the headers of the part.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get the form field name, from the `Content-Disposition` header.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Get the name of the uploaded file, from the `Content-Disposition` header.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Get the content type of the part, from the `Content-Type` header.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get("Content-Type")
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum State {
+    Preamble,
+    Headers,
+    // The number of bytes of the current part's body read so far.
+    Data(usize),
+    Done,
+}
+
+/// A streaming reader for `multipart/form-data` bodies, e.g. file uploads. Only the part being
+/// read is held in memory, so large uploads can be written straight to disk.
+///
+/// Call [MultipartReader::next_part] to move on to the next part, then read its body with
+/// [MultipartReader::read_data] or [MultipartReader::data].
+#[derive(Debug)]
+pub struct MultipartReader<R: Read> {
+    reader: R,
+    delimiter: Vec<u8>,
+    limits: MultipartLimits,
+    buffer: Vec<u8>,
+    eof: bool,
+    parts: usize,
+    state: State,
+}
+
+impl<R: Read> MultipartReader<R> {
+    /// Create a new reader over a multipart body with the given boundary, using the default
+    /// limits.
+    pub fn new(reader: R, boundary: &str) -> Self {
+        Self::with_limits(reader, boundary, MultipartLimits::default())
+    }
+
+    /// Create a new reader over a multipart body with the given boundary and limits.
+    pub fn with_limits(reader: R, boundary: &str, limits: MultipartLimits) -> Self {
+        MultipartReader {
+            reader,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            limits,
+            // Every delimiter is preceded by a line break, except at the very start of the body.
+            buffer: b"\r\n".to_vec(),
+            eof: false,
+            parts: 0,
+            state: State::Preamble,
+        }
+    }
+
+    /// Create a new reader over a multipart body, taking the boundary from the value of the
+    /// `Content-Type` header, e.g. `multipart/form-data; boundary=abc`.
+    pub fn from_content_type(reader: R, content_type: &str) -> Result<Self, Error> {
+        Ok(Self::new(reader, &boundary(content_type)?))
+    }
+
+    /// Move on to the next part, skipping the rest of the current one. Returns `None` once the
+    /// closing delimiter has been read.
+    pub fn next_part(&mut self) -> Result<Option<Part>, Error> {
+        let limit_err = Error {
+            err_type: ErrorType::ContentTooLarge,
+            msg: format!(
+                "Multipart body has more than {} parts",
+                self.limits.max_parts
+            ),
+        };
+
+        loop {
+            match self.state {
+                State::Preamble => self.skip_preamble()?,
+                State::Data(_) => {
+                    let mut scratch = [0; 8192];
+                    while self.read_data(&mut scratch)? > 0 {}
+                }
+                State::Headers => break,
+                State::Done => return Ok(None),
+            }
+        }
+
+        self.parts += 1;
+
+        if self.parts > self.limits.max_parts {
+            return Err(limit_err);
+        }
+
+        let part = self.read_headers()?;
+        self.state = State::Data(0);
+
+        Ok(Some(part))
+    }
+
+    /// Read some of the body of the current part into `buf`, returning the number of bytes read.
+    /// Returns zero at the end of the part.
+    pub fn read_data(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let limit_err = Error {
+            err_type: ErrorType::ContentTooLarge,
+            msg: format!(
+                "Multipart part is larger than {} bytes",
+                self.limits.max_part_size
+            ),
+        };
+
+        let read = match self.state {
+            State::Data(read) => read,
+            _ => return Ok(0),
+        };
+
+        loop {
+            let available = match find(&self.buffer, &self.delimiter) {
+                Some(0) => {
+                    self.buffer.drain(..self.delimiter.len());
+                    self.end_delimiter()?;
+
+                    return Ok(0);
+                }
+                Some(index) => index,
+                // Bytes which could be the start of a delimiter have to wait for more input.
+                None => self.buffer.len().saturating_sub(self.delimiter.len() - 1),
+            };
+
+            if available == 0 {
+                self.fill()?;
+                continue;
+            }
+
+            let len = available.min(buf.len());
+
+            if read + len > self.limits.max_part_size {
+                return Err(limit_err);
+            }
+
+            buf[..len].copy_from_slice(&self.buffer[..len]);
+            self.buffer.drain(..len);
+            self.state = State::Data(read + len);
+
+            return Ok(len);
+        }
+    }
+
+    /// Read the rest of the body of the current part into memory.
+    pub fn data(&mut self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        let mut buf = [0; 8192];
+
+        loop {
+            match self.read_data(&mut buf)? {
+                0 => return Ok(data),
+                len => data.extend_from_slice(&buf[..len]),
+            }
+        }
+    }
+
+    /// Read more input into the buffer. Fails if the input has already ended, since a complete
+    /// multipart body always ends with the closing delimiter.
+    fn fill(&mut self) -> Result<(), Error> {
+        let eof_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Unexpected end of multipart body".to_string(),
+        };
+
+        if self.eof {
+            return Err(eof_err);
+        }
+
+        let mut chunk = [0; 8192];
+
+        let len = self.reader.read(&mut chunk).map_err(|x| Error {
+            err_type: ErrorType::ParserError,
+            msg: format!("Failed to read multipart body: {}", x),
+        })?;
+
+        self.eof = len == 0;
+        self.buffer.extend_from_slice(&chunk[..len]);
+
+        Ok(())
+    }
+
+    fn skip_preamble(&mut self) -> Result<(), Error> {
+        loop {
+            if let Some(index) = find(&self.buffer, &self.delimiter) {
+                self.buffer.drain(..index + self.delimiter.len());
+                return self.end_delimiter();
+            }
+
+            // Only keep enough of the preamble to match a delimiter split across reads.
+            let keep = self.delimiter.len() - 1;
+            if self.buffer.len() > keep {
+                self.buffer.drain(..self.buffer.len() - keep);
+            }
+
+            self.fill()?;
+        }
+    }
+
+    /// Read the rest of the line after a delimiter, which is either `--` for the closing
+    /// delimiter, or optional whitespace followed by a line break. The whitespace is limited to
+    /// `max_header_size` bytes.
+    fn end_delimiter(&mut self) -> Result<(), Error> {
+        let delimiter_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid multipart delimiter".to_string(),
+        };
+
+        let limit_err = Error {
+            err_type: ErrorType::ContentTooLarge,
+            msg: format!(
+                "Multipart delimiter line is longer than {} bytes",
+                self.limits.max_header_size
+            ),
+        };
+
+        while self.buffer.len() < 2 {
+            self.fill()?;
+        }
+
+        if self.buffer.starts_with(b"--") {
+            self.state = State::Done;
+            return Ok(());
+        }
+
+        // Only the bytes read since the last pass are scanned, and anything but whitespace
+        // before the line break fails straight away.
+        let mut index = 0;
+
+        loop {
+            while index < self.buffer.len() {
+                match (self.buffer[index], self.buffer.get(index + 1)) {
+                    (b' ' | b'\t', _) => index += 1,
+                    (b'\r', Some(b'\n')) => {
+                        self.buffer.drain(..index + 2);
+                        self.state = State::Headers;
+
+                        return Ok(());
+                    }
+                    (b'\r', None) => break,
+                    _ => return Err(delimiter_err),
+                }
+            }
+
+            if index > self.limits.max_header_size {
+                return Err(limit_err);
+            }
+
+            self.fill()?;
+        }
+    }
+
+    fn read_headers(&mut self) -> Result<Part, Error> {
+        let limit_err = Error {
+            err_type: ErrorType::ContentTooLarge,
+            msg: format!(
+                "Multipart headers are larger than {} bytes",
+                self.limits.max_header_size
+            ),
+        };
+
+        let head_len = loop {
+            if self.buffer.starts_with(b"\r\n") {
+                break 2;
+            }
+
+            if let Some(index) = find(&self.buffer, b"\r\n\r\n") {
+                break index + 4;
+            }
+
+            if self.buffer.len() > self.limits.max_header_size {
+                return Err(limit_err);
+            }
+
+            self.fill()?;
+        };
+
+        if head_len > self.limits.max_header_size {
+            return Err(limit_err);
+        }
+
+        // Browsers send raw UTF-8 in part headers, e.g. for filenames, so latin-1 is only a
+        // fallback.
+        let head = match String::from_utf8(self.buffer.drain(..head_len).collect()) {
+            Ok(head) => head,
+            Err(err) => message::decode_latin1(err.as_bytes()),
+        };
+
+        let headers: HeaderMap = head
+            .split("\r\n")
+            .filter(|x| !x.is_empty())
+            .map(Header::parse)
+            .collect::<Result<_, _>>()?;
+
+        let (_, parameters) =
+            parse_parameters(headers.get("Content-Disposition").unwrap_or_default());
+
+        let parameter = |name: &str| {
+            parameters
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        Ok(Part {
+            name: parameter("name"),
+            filename: parameter("filename"),
+            headers,
+        })
+    }
+}
+
+/// Get the boundary from the value of a `Content-Type` header. Fails if the media type is not
+/// multipart, or there is no boundary.
+pub(crate) fn boundary(content_type: &str) -> Result<String, Error> {
+    let content_type_err = Error {
+        err_type: ErrorType::InvalidContentType,
+        msg: "Expected a multipart Content-Type with a boundary".to_string(),
+    };
+
+    let (media_type, parameters) = parse_parameters(content_type);
+
+    if !media_type.to_ascii_lowercase().starts_with("multipart/") {
+        return Err(content_type_err);
+    }
+
+    match parameters.into_iter().find(|(name, _)| name == "boundary") {
+        Some((_, boundary)) if (1..=70).contains(&boundary.len()) => Ok(boundary),
+        _ => Err(content_type_err),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|x| x == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorType, MultipartLimits, MultipartReader};
+
+    const BODY: &[u8] = b"preamble\r\n--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nHello\r\n--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n\x00\x01\r\n--\xff\r\n--XyZ--\r\nepilogue";
+
+    #[test]
+    fn read_multipart_body() {
+        let mut reader =
+            MultipartReader::from_content_type(BODY, "multipart/form-data; boundary=\"XyZ\"")
+                .unwrap();
+
+        let part = reader.next_part().unwrap().unwrap();
+        assert_eq!(part.name(), Some("title"));
+        assert_eq!(part.filename(), None);
+        assert_eq!(reader.data().unwrap(), b"Hello");
+
+        let part = reader.next_part().unwrap().unwrap();
+        assert_eq!(part.name(), Some("file"));
+        assert_eq!(part.filename(), Some("a.bin"));
+        assert_eq!(part.content_type(), Some("application/octet-stream"));
+        assert_eq!(reader.data().unwrap(), b"\x00\x01\r\n--\xff");
+
+        assert_eq!(reader.next_part().unwrap(), None);
+    }
+
+    #[test]
+    fn read_multipart_body_one_byte_at_a_time() {
+        struct OneByte<'a>(&'a [u8]);
+
+        impl std::io::Read for OneByte<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match self.0.split_first() {
+                    Some((&byte, rest)) if !buf.is_empty() => {
+                        buf[0] = byte;
+                        self.0 = rest;
+                        Ok(1)
+                    }
+                    _ => Ok(0),
+                }
+            }
+        }
+
+        let mut reader = MultipartReader::new(OneByte(BODY), "XyZ");
+
+        // Skip the first part without reading it.
+        reader.next_part().unwrap().unwrap();
+        reader.next_part().unwrap().unwrap();
+
+        assert_eq!(reader.data().unwrap(), b"\x00\x01\r\n--\xff");
+        assert_eq!(reader.next_part().unwrap(), None);
+    }
+
+    #[test]
+    fn multipart_limits() {
+        let limits = MultipartLimits {
+            max_parts: 1,
+            ..MultipartLimits::default()
+        };
+        let mut reader = MultipartReader::with_limits(BODY, "XyZ", limits);
+        reader.next_part().unwrap();
+        let result = reader.next_part().unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ContentTooLarge);
+
+        let limits = MultipartLimits {
+            max_part_size: 4,
+            ..MultipartLimits::default()
+        };
+        let mut reader = MultipartReader::with_limits(BODY, "XyZ", limits);
+        reader.next_part().unwrap();
+        let result = reader.data().unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ContentTooLarge);
+    }
+
+    #[test]
+    fn read_utf8_filename() {
+        let body = "--XyZ\r\nContent-Disposition: form-data; name=\"cv\"; filename=\"résumé.pdf\"\r\n\r\n%PDF\r\n--XyZ--";
+        let mut reader = MultipartReader::new(body.as_bytes(), "XyZ");
+
+        let part = reader.next_part().unwrap().unwrap();
+        assert_eq!(part.filename(), Some("résumé.pdf"));
+
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"cv\"; filename=\"r\xe9sum\xe9.pdf\"\r\n\r\n%PDF\r\n--XyZ--";
+        let mut reader = MultipartReader::new(&body[..], "XyZ");
+
+        let part = reader.next_part().unwrap().unwrap();
+        assert_eq!(part.filename(), Some("résumé.pdf"));
+    }
+
+    #[test]
+    fn invalid_transport_padding() {
+        let mut body = b"--XyZ".to_vec();
+        body.extend(vec![b'a'; 20 * 1024 * 1024]);
+        let mut reader = MultipartReader::new(&body[..], "XyZ");
+        let result = reader.next_part().unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ParserError);
+
+        let mut body = b"--XyZ".to_vec();
+        body.extend(vec![b' '; 20 * 1024 * 1024]);
+        let mut reader = MultipartReader::new(&body[..], "XyZ");
+        let result = reader.next_part().unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ContentTooLarge);
+
+        let mut reader = MultipartReader::new(&b"--XyZ \t\r\n\r\nHello\r\n--XyZ--"[..], "XyZ");
+        reader.next_part().unwrap().unwrap();
+
+        assert_eq!(reader.data().unwrap(), b"Hello");
+    }
+
+    #[test]
+    fn truncated_multipart_body() {
+        let mut reader = MultipartReader::new(&b"--XyZ\r\n\r\nHello"[..], "XyZ");
+        reader.next_part().unwrap();
+
+        assert!(reader.data().is_err());
+    }
+}
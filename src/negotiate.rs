@@ -0,0 +1,312 @@
+use crate::header::{parse_parameters, split_quoted, Header};
+use crate::status::Status;
+
+/// A single element of an `Accept`, `Accept-Language`, `Accept-Encoding` or `Accept-Charset`
+/// header, e.g. `text/html;level=1;q=0.8`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Preference {
+    value: String,
+    parameters: Vec<(String, String)>,
+    // The quality value in thousandths, so it can be compared exactly.
+    quality: u16,
+}
+
+impl Preference {
+    /// Parse the value of an `Accept-*` header into its elements, in the order they appear.
+    /// Elements with an invalid quality value are skipped.
+    pub fn parse_list(value: &str) -> Vec<Self> {
+        split_quoted(value, ',')
+            .iter()
+            .filter(|x| !x.trim().is_empty())
+            .flat_map(|x| Self::parse(x))
+            .collect()
+    }
+
+    /// Get the value of the preference, e.g. a media range, language range, content coding or
+    /// charset. The value is lowercased.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the parameters of a media range, not including the quality value.
+    pub fn parameters(&self) -> &[(String, String)] {
+        &self.parameters
+    }
+
+    /// Get the quality value, between 0 and 1. Defaults to 1 if not given.
+    pub fn quality(&self) -> f32 {
+        f32::from(self.quality) / 1000.0
+    }
+
+    fn parse(element: &str) -> Option<Self> {
+        let (value, all_parameters) = parse_parameters(element);
+
+        let mut parameters = Vec::new();
+        let mut quality = 1000;
+
+        for (name, value) in all_parameters {
+            // Anything after the quality value is an accept extension, which is ignored.
+            if name == "q" {
+                quality = parse_quality(&value)?;
+                break;
+            }
+
+            parameters.push((name, value));
+        }
+
+        Some(Preference {
+            value: value.to_ascii_lowercase(),
+            parameters,
+            quality,
+        })
+    }
+}
+
+/// The outcome of content negotiation, see [crate::Request::negotiate].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Negotiation {
+    field: &'static str,
+    choice: Option<String>,
+}
+
+impl Negotiation {
+    /// Get the offered value that best matches the request, or `None` if none of them are
+    /// acceptable.
+    pub fn choice(&self) -> Option<&str> {
+        self.choice.as_deref()
+    }
+
+    /// Whether one of the offered values is acceptable.
+    pub fn is_acceptable(&self) -> bool {
+        self.choice.is_some()
+    }
+
+    /// Get the status to respond with, either `200 OK` or `406 Not Acceptable`.
+    pub fn status(&self) -> Status {
+        match self.choice {
+            Some(_) => Status::Ok,
+            None => Status::NotAcceptable,
+        }
+    }
+
+    /// Get the `Vary` header to add to the response, naming the request header the choice was
+    /// based on.
+    pub fn vary(&self) -> Header {
+        Header::new("Vary", self.field)
+    }
+}
+
+/// The request header content is negotiated over.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Kind {
+    MediaType,
+    Language,
+    Encoding,
+    Charset,
+}
+
+impl Kind {
+    pub(crate) fn field(self) -> &'static str {
+        match self {
+            Kind::MediaType => "Accept",
+            Kind::Language => "Accept-Language",
+            Kind::Encoding => "Accept-Encoding",
+            Kind::Charset => "Accept-Charset",
+        }
+    }
+
+    /// Get how specifically `preference` matches `offered`, or `None` if it doesn't match.
+    fn specificity(self, preference: &Preference, offered: &str) -> Option<usize> {
+        let range = preference.value();
+
+        if range == "*" && self != Kind::MediaType {
+            return Some(0);
+        }
+
+        match self {
+            Kind::MediaType => {
+                let (media_type, parameters) = parse_parameters(offered);
+                let media_type = media_type.to_ascii_lowercase();
+
+                if range == "*/*" {
+                    return Some(0);
+                }
+
+                if let Some(main) = range.strip_suffix("/*") {
+                    return match media_type.split_once('/') {
+                        Some((x, _)) if x == main => Some(1),
+                        _ => None,
+                    };
+                }
+
+                let parameters_match = preference.parameters().iter().all(|(name, value)| {
+                    parameters
+                        .iter()
+                        .any(|(x, y)| x == name && y.eq_ignore_ascii_case(value))
+                });
+
+                (media_type == range && parameters_match)
+                    .then_some(2 + preference.parameters().len())
+            }
+            Kind::Language => {
+                let offered = offered.to_ascii_lowercase();
+
+                let matches = offered == range
+                    || offered
+                        .strip_prefix(range)
+                        .is_some_and(|x| x.starts_with('-'));
+
+                matches.then_some(1 + range.len())
+            }
+            Kind::Encoding | Kind::Charset => offered.eq_ignore_ascii_case(range).then_some(1),
+        }
+    }
+
+    /// Get the quality of `offered` in thousandths, from the most specific matching preference.
+    fn quality(self, preferences: &[Preference], offered: &str) -> u16 {
+        let best = preferences
+            .iter()
+            .filter_map(|x| Some((self.specificity(x, offered)?, x.quality)))
+            .max_by_key(|(specificity, _)| *specificity);
+
+        match best {
+            Some((_, quality)) => quality,
+            // The identity coding is acceptable unless it has been explicitly excluded, but any
+            // coding the client did ask for is preferred over it.
+            None if self == Kind::Encoding && offered.eq_ignore_ascii_case("identity") => 1,
+            None => 0,
+        }
+    }
+}
+
+/// Pick the offered value with the highest quality, given the values of the request header for
+/// `kind`. Ties go to the value offered first. If the request doesn't have the header, the first
+/// offered value is picked.
+pub(crate) fn negotiate(kind: Kind, values: &[&str], offered: &[&str]) -> Negotiation {
+    let choice = if values.is_empty() {
+        offered.first()
+    } else {
+        let preferences = Preference::parse_list(&values.join(","));
+
+        offered
+            .iter()
+            .map(|x| (x, kind.quality(&preferences, x)))
+            .filter(|(_, quality)| *quality > 0)
+            .rev()
+            .max_by_key(|(_, quality)| *quality)
+            .map(|(x, _)| x)
+    };
+
+    Negotiation {
+        field: kind.field(),
+        choice: choice.map(|x| x.to_string()),
+    }
+}
+
+/// Parse a quality value, e.g. `0.8`, into thousandths.
+fn parse_quality(value: &str) -> Option<u16> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+
+    if fraction.len() > 3 || !fraction.chars().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    let fraction = format!("{:0<3}", fraction).parse::<u16>().ok()?;
+
+    match whole {
+        "0" => Some(fraction),
+        "1" if fraction == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::negotiate::{negotiate, Kind};
+    use crate::Preference;
+
+    #[test]
+    fn parse_preference_list() {
+        let result = Preference::parse_list("text/html;level=1;q=0.5;ext=x, */*;q=0, text/plain");
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].value(), "text/html");
+        assert_eq!(
+            result[0].parameters(),
+            &[("level".to_string(), "1".to_string())]
+        );
+        assert_eq!(result[0].quality(), 0.5);
+        assert_eq!(result[1].quality(), 0.0);
+        assert_eq!(result[2].quality(), 1.0);
+    }
+
+    #[test]
+    fn parse_preference_list_skips_invalid_quality() {
+        let result = Preference::parse_list("a;q=2, b;q=0.1234, c;q=1.000");
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].value(), "c");
+    }
+
+    #[test]
+    fn negotiate_media_type() {
+        let offered = ["application/json", "text/csv", "text/html"];
+
+        let result = negotiate(Kind::MediaType, &["text/*;q=0.5, text/csv"], &offered);
+        assert_eq!(result.choice(), Some("text/csv"));
+
+        let result = negotiate(Kind::MediaType, &["*/*;q=0.1, text/html"], &offered);
+        assert_eq!(result.choice(), Some("text/html"));
+
+        // Ties go to the first offered value.
+        let result = negotiate(Kind::MediaType, &["*/*"], &offered);
+        assert_eq!(result.choice(), Some("application/json"));
+
+        let result = negotiate(Kind::MediaType, &["text/*, text/csv;q=0"], &offered);
+        assert_eq!(result.choice(), Some("text/html"));
+
+        let result = negotiate(Kind::MediaType, &["image/png"], &offered);
+        assert_eq!(result.choice(), None);
+        assert_eq!(result.status().code(), 406);
+        assert_eq!(result.vary().to_string(), "Vary: Accept");
+    }
+
+    #[test]
+    fn negotiate_without_header() {
+        let result = negotiate(Kind::MediaType, &[], &["text/html", "text/plain"]);
+
+        assert_eq!(result.choice(), Some("text/html"));
+    }
+
+    #[test]
+    fn negotiate_language() {
+        let offered = ["en", "en-GB", "fr"];
+
+        let result = negotiate(Kind::Language, &["en-gb, en;q=0.8"], &offered);
+        assert_eq!(result.choice(), Some("en-GB"));
+
+        let result = negotiate(Kind::Language, &["fr-CA, fr;q=0.9, *;q=0.1"], &offered);
+        assert_eq!(result.choice(), Some("fr"));
+    }
+
+    #[test]
+    fn negotiate_encoding() {
+        let offered = ["gzip", "identity"];
+
+        let result = negotiate(Kind::Encoding, &["br"], &offered);
+        assert_eq!(result.choice(), Some("identity"));
+
+        let result = negotiate(Kind::Encoding, &["br, gzip;q=0.5"], &offered);
+        assert_eq!(result.choice(), Some("gzip"));
+
+        let result = negotiate(Kind::Encoding, &["br, identity;q=0"], &offered);
+        assert_eq!(result.choice(), None);
+    }
+
+    #[test]
+    fn negotiate_charset() {
+        let result = negotiate(Kind::Charset, &["iso-8859-1, UTF-8;q=0.9"], &["utf-8"]);
+
+        assert_eq!(result.choice(), Some("utf-8"));
+    }
+}
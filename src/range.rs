@@ -0,0 +1,182 @@
+use crate::error::{Error, ErrorType};
+
+/// The most ranges a request may ask for before the whole representation is sent instead.
+pub(crate) const MAX_RANGES: usize = 16;
+
+/// A single range of a `Range: bytes=...` request header.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ByteRange {
+    /// A range with a first and last byte position, both inclusive, e.g. `0-499`.
+    Span(u64, u64),
+    /// An open-ended range, from a byte position to the end, e.g. `9500-`.
+    From(u64),
+    /// The given number of bytes at the end, e.g. `-500`.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Parse the value of a `Range` header, e.g. `bytes=0-499, -500`. Fails if the unit is not
+    /// `bytes` or any of the ranges are malformed.
+    pub fn parse_header(value: &str) -> Result<Vec<Self>, Error> {
+        let range_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid Range header".to_string(),
+        };
+
+        let ranges = match value.trim().split_once('=') {
+            Some((unit, ranges)) if unit.trim().eq_ignore_ascii_case("bytes") => ranges,
+            _ => return Err(range_err),
+        };
+
+        let ranges = ranges
+            .split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(|x| Self::parse(x).ok_or_else(|| range_err.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if ranges.is_empty() {
+            return Err(range_err);
+        }
+
+        Ok(ranges)
+    }
+
+    /// Resolve the range against a representation of `len` bytes, giving the first and last byte
+    /// positions, both inclusive. Returns `None` if the range can't be satisfied.
+    pub fn resolve(&self, len: u64) -> Option<(u64, u64)> {
+        match *self {
+            ByteRange::Span(first, last) if first < len => Some((first, last.min(len - 1))),
+            ByteRange::From(first) if first < len => Some((first, len - 1)),
+            ByteRange::Suffix(suffix) if suffix > 0 && len > 0 => {
+                Some((len - suffix.min(len), len - 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(range: &str) -> Option<Self> {
+        let (first, last) = range.split_once('-')?;
+
+        match (parse_position(first), parse_position(last)) {
+            (Some(first), Some(last)) if first <= last => Some(ByteRange::Span(first, last)),
+            (Some(first), None) if last.is_empty() => Some(ByteRange::From(first)),
+            (None, Some(suffix)) if first.is_empty() => Some(ByteRange::Suffix(suffix)),
+            _ => None,
+        }
+    }
+}
+
+/// Sort resolved ranges and merge any that overlap or are adjacent, so no byte is sent twice.
+pub(crate) fn coalesce(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+
+    for (first, last) in ranges {
+        match merged.last_mut() {
+            Some(previous) if first <= previous.1.saturating_add(1) => {
+                previous.1 = previous.1.max(last)
+            }
+            _ => merged.push((first, last)),
+        }
+    }
+
+    merged
+}
+
+/// Encode the given ranges of `content` as a `multipart/byteranges` body. Returns the body and
+/// the boundary, which is picked so it doesn't appear in the content.
+pub(crate) fn encode_byteranges(
+    content: &[u8],
+    content_type: &str,
+    ranges: &[(u64, u64)],
+) -> (Vec<u8>, String) {
+    let mut boundary = "nanohttp-byteranges".to_string();
+    let mut count = 0;
+
+    while content
+        .windows(boundary.len())
+        .any(|x| x == boundary.as_bytes())
+    {
+        count += 1;
+        boundary = format!("nanohttp-byteranges-{}", count);
+    }
+
+    let mut encoded = Vec::new();
+
+    for &(first, last) in ranges {
+        let head = format!(
+            "--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+            boundary,
+            content_type,
+            first,
+            last,
+            content.len()
+        );
+
+        encoded.extend_from_slice(head.as_bytes());
+        encoded.extend_from_slice(&content[first as usize..=last as usize]);
+        encoded.extend_from_slice(b"\r\n");
+    }
+
+    encoded.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+    (encoded, boundary)
+}
+
+fn parse_position(value: &str) -> Option<u64> {
+    let value = value.trim();
+
+    if value.is_empty() || !value.chars().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::coalesce;
+    use crate::ByteRange;
+
+    #[test]
+    fn parse_range_header() {
+        let result = ByteRange::parse_header("bytes=0-499, 9500-,-500").unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                ByteRange::Span(0, 499),
+                ByteRange::From(9500),
+                ByteRange::Suffix(500)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_range_header() {
+        assert!(ByteRange::parse_header("items=0-1").is_err());
+        assert!(ByteRange::parse_header("bytes=5-1").is_err());
+        assert!(ByteRange::parse_header("bytes=-").is_err());
+        assert!(ByteRange::parse_header("bytes=").is_err());
+        assert!(ByteRange::parse_header("bytes=a-b").is_err());
+    }
+
+    #[test]
+    fn resolve_ranges() {
+        assert_eq!(ByteRange::Span(0, 499).resolve(100), Some((0, 99)));
+        assert_eq!(ByteRange::Span(100, 199).resolve(100), None);
+        assert_eq!(ByteRange::From(90).resolve(100), Some((90, 99)));
+        assert_eq!(ByteRange::Suffix(500).resolve(100), Some((0, 99)));
+        assert_eq!(ByteRange::Suffix(10).resolve(100), Some((90, 99)));
+        assert_eq!(ByteRange::Suffix(0).resolve(100), None);
+    }
+
+    #[test]
+    fn coalesce_ranges() {
+        let result = coalesce(vec![(50, 59), (0, 9), (5, 19), (20, 29), (40, 45)]);
+
+        assert_eq!(result, vec![(0, 29), (40, 45), (50, 59)]);
+    }
+}
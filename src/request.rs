@@ -1,6 +1,21 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::from_utf8;
+use std::time::SystemTime;
+
+use crate::chunked::{self, ChunkedDecoder};
+use crate::conditional::{self, ETag, Precondition};
+use crate::config::ParserConfig;
+use crate::cookie::Cookie;
+use crate::encoding::{decode_pair, percent_decode_bytes, percent_encode};
 use crate::error::{Error, ErrorType};
-use crate::header::Header;
+use crate::header::{Header, HeaderMap};
+use crate::message::{self, Framing};
 use crate::method::Method;
+use crate::multipart::{self, MultipartReader};
+use crate::negotiate::{self, Kind, Negotiation};
+use crate::range::ByteRange;
+use crate::uri::Uri;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Query {
@@ -8,6 +23,18 @@ pub struct Query {
     value: String,
 }
 
+impl Query {
+    /// Get the percent-decoded key of the query pair.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the percent-decoded value of the query pair. Empty if the pair has no `=`.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Path {
     pub uri: String,
@@ -15,163 +42,373 @@ pub struct Path {
 }
 
 impl Path {
-    fn from_string(path: &str) -> Self {
-        let mut parts = path.split("?");
+    /// Build the path from a parsed request target. `offset` is the position of the query string
+    /// in the message, and is used to report the position of malformed query pairs in strict
+    /// mode.
+    fn from_uri(uri: &Uri, offset: usize, config: &ParserConfig) -> Result<Self, Error> {
+        let mut query = Vec::new();
+        let mut pair_offset = offset;
 
-        let uri = match parts.next() {
-            Some(uri) => uri,
-            None => {
-                return Path {
-                    uri: path.to_string(),
-                    query: Vec::new(),
-                }
-            }
-        };
-
-        let query_string = match parts.next() {
-            Some("") => {
-                return Path {
-                    uri: uri.to_string(),
-                    query: Vec::new(),
-                }
-            }
-            Some(query_string) => query_string,
-            None => {
-                return Path {
-                    uri: path.to_string(),
-                    query: Vec::new(),
+        for pair in uri.query.as_deref().unwrap_or_default().split('&') {
+            if !pair.is_empty() {
+                match Self::parse_query(pair) {
+                    Ok(pair) => query.push(pair),
+                    Err(err) if config.strict => return Err(err.at(1, pair_offset)),
+                    Err(_) => (),
                 }
             }
-        };
 
-        let query: Vec<Query> = query_string
-            .split("&")
-            .flat_map(|x| Self::parse_query(x))
-            .collect();
+            pair_offset += pair.len() + 1;
+        }
 
-        Path {
-            uri: uri.to_string(),
+        Ok(Path {
+            uri: uri.path.clone(),
             query,
-        }
+        })
     }
 
-    fn parse_query(query: &str) -> Result<Query, Error> {
-        let parser_err = Error {
-            err_type: ErrorType::ParserError,
-            msg: "Invalid query string format".to_string(),
-        };
+    /// Get the value of the first query pair with the given key.
+    pub fn query_get(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|x| x.key == key)
+            .map(|x| x.value.as_str())
+    }
 
-        let mut parts = query.split("=");
+    /// Get the values of every query pair with the given key, in order, e.g. for `?tag=a&tag=b`.
+    pub fn query_all(&self, key: &str) -> Vec<&str> {
+        self.query
+            .iter()
+            .filter(|x| x.key == key)
+            .map(|x| x.value.as_str())
+            .collect()
+    }
 
-        let key = match parts.next() {
-            Some(key) => key,
-            None => return Err(parser_err),
-        };
+    /// Get the percent-decoded segments of the uri, e.g. `/users/john%20doe` yields `users` and
+    /// `john doe`. Segments which don't decode to valid UTF-8 are decoded lossily.
+    pub fn segments(&self) -> impl Iterator<Item = String> + '_ {
+        self.uri
+            .trim_start_matches('/')
+            .split('/')
+            .filter(|x| !x.is_empty())
+            .map(|x| match percent_decode_bytes(x, false) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(_) => x.to_string(),
+            })
+    }
 
-        let value = match parts.next() {
-            Some(value) => value,
-            None => "",
-        };
+    fn parse_query(query: &str) -> Result<Query, Error> {
+        let (key, value) = decode_pair(query)?;
 
-        Ok(Query {
-            key: key.to_string(),
-            value: value.to_string(),
-        })
+        Ok(Query { key, value })
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Request {
     pub method: Method,
+    pub target: Uri,
     pub path: Path,
     pub scheme: String,
     pub version: String,
-    pub headers: Vec<Header>,
-    pub body: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub trailers: HeaderMap,
 }
 
 impl Request {
     /// Parse a http plaintext request into a `Request` object.
     pub fn from_string(buffer: &str) -> Result<Self, Error> {
-        Self::parse(buffer)
+        Self::from_bytes(buffer.as_bytes())
     }
 
-    fn parse(buffer: &str) -> Result<Request, Error> {
-        let parser_err = Error {
-            err_type: ErrorType::ParserError,
-            msg: "Invalid request format".to_string(),
-        };
+    /// Parse a raw http request into a `Request` object. The start line and headers are decoded
+    /// as latin-1, and the body is kept as raw bytes, so binary bodies are preserved.
+    ///
+    /// The body is exactly the number of bytes given by the `Content-Length` header, or is decoded
+    /// from `Transfer-Encoding: chunked`, and any bytes after it are ignored. Use
+    /// [Request::from_bytes_with_remainder] to get them back.
+    ///
+    /// Parsing is strict, so any malformed line is an error. See [Request::from_bytes_with_config]
+    /// to parse leniently.
+    pub fn from_bytes(buffer: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_config(buffer, &ParserConfig::default())
+    }
 
-        let mut parts = buffer.split("\r\n");
+    /// Parse a raw http request in the same way as [Request::from_bytes], using the given config.
+    pub fn from_bytes_with_config(buffer: &[u8], config: &ParserConfig) -> Result<Self, Error> {
+        let (request, _) = Self::parse(buffer, config)?;
 
-        let start_line = match parts.next() {
-            Some(start_line) => start_line,
-            None => return Err(parser_err),
+        Ok(request)
+    }
+
+    /// Parse a raw http request in the same way as [Request::from_bytes], but also return the
+    /// bytes left over after the request, e.g. the start of the next pipelined request.
+    pub fn from_bytes_with_remainder(buffer: &[u8]) -> Result<(Self, &[u8]), Error> {
+        Self::parse(buffer, &ParserConfig::default())
+    }
+
+    /// Start building a new http request, e.g. to send to an upstream service. The uri can be in
+    /// any request target form, and may include a query string.
+    pub fn builder(method: Method, uri: &str) -> RequestBuilder {
+        RequestBuilder {
+            method,
+            uri: uri.to_string(),
+            query: Vec::new(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Convert the `Request` to a valid raw http request. The body is chunk encoded if the
+    /// `Transfer-Encoding` header is set to chunked, along with any trailers. Any fragment of the
+    /// target is left out, since it is never sent. The head is written as latin-1, the same way
+    /// it is parsed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = message::encode_latin1(&self.head());
+        bytes.extend_from_slice(&self.encoded_body());
+
+        bytes
+    }
+
+    /// Get the cookies sent with the request in the `Cookie` header. Malformed pairs are skipped.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.headers
+            .get_all("Cookie")
+            .into_iter()
+            .flat_map(Cookie::parse_header)
+            .collect()
+    }
+
+    /// Get the ranges requested in the `Range` header, or an empty list if there is no `Range`
+    /// header. Fails if the header is malformed.
+    pub fn ranges(&self) -> Result<Vec<ByteRange>, Error> {
+        match self.headers.get("Range") {
+            Some(range) => ByteRange::parse_header(range),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Check the `If-Range` header against the current validator of the resource, either its
+    /// `ETag` or its `Last-Modified` date. Returns `true` if there is no `If-Range` header, or if
+    /// it matches exactly. Weak entity tags never match.
+    pub fn if_range(&self, validator: &str) -> bool {
+        match self.headers.get("If-Range") {
+            Some(if_range) => !if_range.starts_with("W/") && if_range == validator,
+            None => true,
+        }
+    }
+
+    /// Evaluate the `If-Match`, `If-None-Match`, `If-Modified-Since` and `If-Unmodified-Since`
+    /// headers against the current `ETag` and `Last-Modified` time of the resource. Use
+    /// [Precondition::apply] to turn the outcome into a response.
+    pub fn preconditions(
+        &self,
+        etag: Option<&ETag>,
+        last_modified: Option<SystemTime>,
+    ) -> Precondition {
+        conditional::evaluate(self, etag, last_modified)
+    }
+
+    /// Decode a `application/x-www-form-urlencoded` body, e.g. from a html form, into its key-value
+    /// pairs. Keys can be repeated. Fails if the `Content-Type` header is not set to
+    /// `application/x-www-form-urlencoded`, or if the body is not correctly encoded.
+    pub fn form(&self) -> Result<Vec<Query>, Error> {
+        let content_type_err = Error {
+            err_type: ErrorType::InvalidContentType,
+            msg: "Expected Content-Type application/x-www-form-urlencoded".to_string(),
         };
 
-        let (method, path, scheme, version) = Self::parse_start_line(start_line)?;
+        let content_type = self.headers.get("Content-Type").unwrap_or_default();
+        let media_type = content_type.split(';').next().unwrap_or_default();
 
-        let headers: Vec<Header> = parts
-            .clone()
-            .take_while(|x| x.to_owned() != "")
-            .flat_map(|x| Self::parse_header(x))
-            .collect();
+        if !media_type
+            .trim()
+            .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        {
+            return Err(content_type_err);
+        }
 
-        let body: String = parts.clone().skip_while(|x| x.to_owned() != "").collect();
+        self.body_str()?
+            .split('&')
+            .filter(|x| !x.is_empty())
+            .map(Path::parse_query)
+            .collect()
+    }
 
-        Ok(Request {
-            method,
-            path: Path::from_string(path),
-            scheme: scheme.to_string(),
-            version: version.to_string(),
-            headers,
-            body,
+    /// Read the body of the request as `multipart/form-data`, taking the boundary from the
+    /// `Content-Type` header. Fails if the request is not multipart or has no boundary.
+    pub fn multipart(&self) -> Result<MultipartReader<&[u8]>, Error> {
+        let content_type = self.headers.get("Content-Type").unwrap_or_default();
+        let boundary = multipart::boundary(content_type)?;
+
+        Ok(MultipartReader::new(&self.body[..], &boundary))
+    }
+
+    /// Pick the best of the offered media types, e.g. `["application/json", "text/html"]`, based
+    /// on the `Accept` header. Add [Negotiation::vary] to the response.
+    pub fn negotiate(&self, offered: &[&str]) -> Negotiation {
+        self.negotiate_by(Kind::MediaType, offered)
+    }
+
+    /// Pick the best of the offered languages based on the `Accept-Language` header.
+    pub fn negotiate_language(&self, offered: &[&str]) -> Negotiation {
+        self.negotiate_by(Kind::Language, offered)
+    }
+
+    /// Pick the best of the offered content codings based on the `Accept-Encoding` header.
+    pub fn negotiate_encoding(&self, offered: &[&str]) -> Negotiation {
+        self.negotiate_by(Kind::Encoding, offered)
+    }
+
+    /// Pick the best of the offered charsets based on the `Accept-Charset` header.
+    pub fn negotiate_charset(&self, offered: &[&str]) -> Negotiation {
+        self.negotiate_by(Kind::Charset, offered)
+    }
+
+    /// Get the body of the request as a string slice. Fails if the body is not valid UTF-8.
+    pub fn body_str(&self) -> Result<&str, Error> {
+        from_utf8(&self.body).map_err(|_| Error {
+            err_type: ErrorType::InvalidEncoding,
+            msg: "Request body is not valid UTF-8".to_string(),
         })
     }
 
-    fn parse_header(line: &str) -> Result<Header, Error> {
-        let parser_err = Error {
-            err_type: ErrorType::ParserError,
-            msg: "Invalid header format".to_string(),
-        };
+    fn negotiate_by(&self, kind: Kind, offered: &[&str]) -> Negotiation {
+        negotiate::negotiate(kind, &self.headers.get_all(kind.field()), offered)
+    }
 
-        let mut parts = line.split(": ");
+    fn parse<'a>(buffer: &'a [u8], config: &ParserConfig) -> Result<(Request, &'a [u8]), Error> {
+        let head_len = message::find_head_end(buffer).unwrap_or(buffer.len());
 
-        let key = match parts.next() {
-            Some(key) => key,
-            None => return Err(parser_err),
-        };
+        let mut request = Self::parse_head(&buffer[..head_len], config)?;
 
-        let value = match parts.next() {
-            Some(value) => value,
-            None => return Err(parser_err),
+        // Requests without any framing headers have no body.
+        let framing = message::framing(&request.headers)?.unwrap_or(Framing::Length(0));
+
+        if let Framing::Length(len) = framing {
+            message::check_body_len(len, config)?;
+        }
+
+        let (body, trailers, len) = message::read_body(&buffer[head_len..], framing, config)?;
+        message::check_body_len(body.len(), config)?;
+
+        request.body = body;
+        request.trailers = trailers;
+
+        let body_end = head_len + len;
+
+        Ok((request, &buffer[body_end..]))
+    }
+
+    /// Parse the start line and headers of a request. The returned request has an empty body.
+    fn head(&self) -> String {
+        let headers = self
+            .headers
+            .iter()
+            .filter(|x| x.has_valid_name())
+            .fold(String::new(), |a, b| a + &b.to_string() + "\r\n");
+
+        let target = Uri {
+            fragment: None,
+            ..self.target.clone()
         };
 
-        Ok(Header::new(key, value))
+        format!(
+            "{} {} {}/{}\r\n{}\r\n",
+            self.method, target, self.scheme, self.version, headers
+        )
     }
 
-    fn parse_protocol(line: &str) -> Result<(&str, &str), Error> {
+    fn encoded_body(&self) -> Cow<'_, [u8]> {
+        match message::framing(&self.headers) {
+            Ok(Some(Framing::Chunked)) => Cow::Owned(chunked::encode(&self.body, &self.trailers)),
+            _ => Cow::Borrowed(&self.body),
+        }
+    }
+
+    fn parse_head(head: &[u8], config: &ParserConfig) -> Result<Request, Error> {
         let parser_err = Error {
             err_type: ErrorType::ParserError,
-            msg: "Invalid protocol format".to_string(),
+            msg: "Invalid request format".to_string(),
         };
 
-        let mut parts = line.split("/");
+        let request_line_err = Error {
+            err_type: ErrorType::RequestLineTooLong,
+            msg: format!(
+                "Request line longer than {} bytes",
+                config.max_request_line_len
+            ),
+        };
 
-        let scheme = match parts.next() {
-            Some(scheme) => scheme,
-            None => return Err(parser_err),
+        let uri_err = Error {
+            err_type: ErrorType::UriTooLong,
+            msg: format!("Request target longer than {} bytes", config.max_uri_len),
         };
 
-        let version = match parts.next() {
-            Some(version) => version,
+        let encoding_err = Error {
+            err_type: ErrorType::InvalidFraming,
+            msg: "Transfer-Encoding in a HTTP/1.0 request".to_string(),
+        };
+
+        let head = message::replace_control_chars(message::decode_latin1(head), config)?;
+
+        let mut parts = head.split("\r\n");
+
+        let start_line = match parts.next() {
+            Some(start_line) => start_line,
             None => return Err(parser_err),
         };
 
-        Ok((scheme, version))
+        if message::byte_len(start_line) > config.max_request_line_len {
+            return Err(request_line_err);
+        }
+
+        let (method, target, scheme, version) = Self::parse_start_line(start_line, config)?;
+
+        // The target always follows the method and a single space.
+        let target_offset = start_line.find(' ').map_or(0, |x| x + 1);
+
+        if message::byte_len(target) > config.max_uri_len {
+            return Err(uri_err.at(1, target_offset));
+        }
+
+        let uri = match Uri::from_string(target) {
+            Ok(uri) => uri,
+            Err(err) if config.strict => return Err(err.at(1, target_offset)),
+            Err(_) => Uri::origin(target),
+        };
+
+        let query_offset = target_offset
+            + target
+                .find('?')
+                .map_or(0, |x| message::byte_len(&target[..x]) + 1);
+        let path = Path::from_uri(&uri, query_offset, config)?;
+
+        let mut headers = message::parse_headers(parts, message::byte_len(start_line) + 2, config)?;
+        message::check_request_framing(&mut headers)?;
+
+        // HTTP/1.0 has no chunked encoding, so the framing of such a request can't be trusted.
+        if version == "1.0" && headers.contains("Transfer-Encoding") {
+            return Err(encoding_err);
+        }
+
+        Ok(Request {
+            method,
+            target: uri,
+            path,
+            scheme: scheme.to_string(),
+            version: version.to_string(),
+            headers,
+            body: Vec::new(),
+            trailers: HeaderMap::new(),
+        })
     }
 
-    fn parse_start_line(line: &str) -> Result<(Method, &str, &str, &str), Error> {
+    fn parse_start_line<'a>(
+        line: &'a str,
+        config: &ParserConfig,
+    ) -> Result<(Method, &'a str, &'a str, &'a str), Error> {
         let parser_err = Error {
             err_type: ErrorType::ParserError,
             msg: "Invalid start line format".to_string(),
@@ -180,31 +417,283 @@ impl Request {
         let mut parts = line.split(" ");
 
         let method = match parts.next() {
-            Some(method) => Method::from_string(method)?,
-            None => return Err(parser_err),
+            Some(method) => Method::from_string(method).map_err(|x| x.at(1, 0))?,
+            None => return Err(parser_err.at(1, 0)),
         };
 
         let path = match parts.next() {
             Some(path) => path,
-            None => return Err(parser_err),
+            None => return Err(parser_err.at(1, line.len())),
         };
 
         let protocol = match parts.next() {
             Some(protocol) => protocol,
-            None => return Err(parser_err),
+            None => return Err(parser_err.at(1, line.len())),
         };
 
-        let (scheme, version) = Self::parse_protocol(protocol)?;
+        if config.strict && (path.is_empty() || parts.next().is_some()) {
+            return Err(parser_err.at(1, 0));
+        }
+
+        let protocol_offset = line.len() - protocol.len();
+        let (scheme, version) =
+            message::parse_protocol(protocol, config).map_err(|x| x.at(1, protocol_offset))?;
 
         Ok((method, path, scheme, version))
     }
 }
 
+impl fmt::Display for Request {
+    /// Convert the `Request` to a valid http plaintext request. A body which isn't valid UTF-8 is
+    /// converted lossily, see [Request::to_bytes] to keep it intact.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let body = self.encoded_body();
+
+        write!(f, "{}{}", self.head(), String::from_utf8_lossy(&body))
+    }
+}
+
+/// A builder for outgoing http requests, created with [Request::builder].
+#[derive(Debug, PartialEq, Clone)]
+pub struct RequestBuilder {
+    method: Method,
+    uri: String,
+    query: Vec<(String, String)>,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl RequestBuilder {
+    /// Add a header to the request, keeping any existing headers with the same name.
+    pub fn header(self, header: Header) -> Self {
+        let mut headers = self.headers;
+        headers.append(header);
+
+        RequestBuilder { headers, ..self }
+    }
+
+    /// Add a query pair to the request. The key and value are percent-encoded when the request
+    /// is built.
+    pub fn query(self, key: &str, value: &str) -> Self {
+        let mut query = self.query;
+        query.push((key.to_string(), value.to_string()));
+
+        RequestBuilder { query, ..self }
+    }
+
+    /// Set the body of the request.
+    pub fn body(self, body: impl AsRef<[u8]>) -> Self {
+        RequestBuilder {
+            body: body.as_ref().to_vec(),
+            ..self
+        }
+    }
+
+    /// Build the request. Sets the `Host` header from the uri if it has an authority, and sets the
+    /// `Content-Length` header if there is a body and no framing headers have been set. Fails if
+    /// the uri or the name of a header is invalid.
+    pub fn build(self) -> Result<Request, Error> {
+        let mut uri = Uri::with_fragment(&self.uri)?;
+
+        for header in self.headers.iter() {
+            Header::try_new(header.name(), header.value())?;
+        }
+
+        if !self.query.is_empty() {
+            let query: Vec<String> = self
+                .query
+                .iter()
+                .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+                .collect();
+
+            uri.query = match uri.query {
+                Some(existing) if !existing.is_empty() => {
+                    Some(format!("{}&{}", existing, query.join("&")))
+                }
+                _ => Some(query.join("&")),
+            };
+        }
+
+        let path = Path::from_uri(&uri, 0, &ParserConfig::strict())?;
+
+        let mut headers = self.headers;
+
+        if !headers.contains("Host") {
+            if let Some(authority) = uri.authority() {
+                headers.append(Header::new("Host", &authority));
+            }
+        }
+
+        let framed = headers.contains("Content-Length") || headers.contains("Transfer-Encoding");
+
+        if !self.body.is_empty() && !framed {
+            headers.append(Header::new("Content-Length", &self.body.len().to_string()));
+        }
+
+        Ok(Request {
+            method: self.method,
+            target: uri,
+            path,
+            scheme: "HTTP".to_string(),
+            version: "1.1".to_string(),
+            headers,
+            body: self.body,
+            trailers: HeaderMap::new(),
+        })
+    }
+}
+
+/// The result of feeding bytes into a [RequestParser].
+// Boxing the request would only save a copy once per message, at the cost of a worse api.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq)]
+pub enum ParseStatus {
+    /// The message is not complete yet, more bytes are needed.
+    Partial,
+    /// A complete request has been parsed. `consumed` is the number of bytes the request took up
+    /// in the input. Any bytes after that are kept by the parser for the next request.
+    Complete { request: Request, consumed: usize },
+}
+
+#[derive(Debug)]
+enum PendingBody {
+    Length(usize),
+    // The decoder, and the number of body bytes it has consumed so far.
+    Chunked(ChunkedDecoder, usize),
+}
+
+/// An incremental http request parser. Chunks of bytes are fed in as they are read from a
+/// socket, and the parser reports whether a complete request has been received yet.
+///
+/// The body is framed by the `Content-Length` header, or decoded from `Transfer-Encoding:
+/// chunked`. Requests with neither have no body.
+#[derive(Debug, Default)]
+pub struct RequestParser {
+    config: ParserConfig,
+    buffer: Vec<u8>,
+    // The parsed head of the current request, along with the length of the head.
+    pending: Option<(Request, usize, PendingBody)>,
+}
+
+impl RequestParser {
+    /// Create a new parser with an empty buffer, which parses strictly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new parser with an empty buffer, using the given config.
+    pub fn with_config(config: ParserConfig) -> Self {
+        RequestParser {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Feed a chunk of bytes into the parser. Returns [ParseStatus::Complete] once a full request
+    /// has been received. Bytes following the request are kept, so an empty chunk can be fed in
+    /// to parse the next pipelined request.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<ParseStatus, Error> {
+        self.buffer.extend_from_slice(chunk);
+
+        let (mut request, head_len, mut body) = match self.pending.take() {
+            Some(pending) => pending,
+            None => {
+                let head_len = match message::find_head_end(&self.buffer) {
+                    Some(head_len) => head_len,
+                    None => {
+                        self.check_partial_head()?;
+                        return Ok(ParseStatus::Partial);
+                    }
+                };
+
+                let request = Request::parse_head(&self.buffer[..head_len], &self.config)?;
+
+                let body = match message::framing(&request.headers)? {
+                    Some(Framing::Length(len)) => {
+                        message::check_body_len(len, &self.config)?;
+                        PendingBody::Length(len)
+                    }
+                    Some(Framing::Chunked) => {
+                        PendingBody::Chunked(ChunkedDecoder::with_config(&self.config), 0)
+                    }
+                    None => PendingBody::Length(0),
+                };
+
+                (request, head_len, body)
+            }
+        };
+
+        let (consumed, done) = match &mut body {
+            PendingBody::Length(len) => {
+                let consumed = head_len + *len;
+
+                (consumed, self.buffer.len() >= consumed)
+            }
+            PendingBody::Chunked(decoder, offset) => {
+                *offset += decoder.feed(&self.buffer[head_len + *offset..])?;
+                message::check_body_len(decoder.body_len(), &self.config)?;
+
+                (head_len + *offset, decoder.is_done())
+            }
+        };
+
+        if !done {
+            self.pending = Some((request, head_len, body));
+            return Ok(ParseStatus::Partial);
+        }
+
+        match body {
+            PendingBody::Length(_) => request.body = self.buffer[head_len..consumed].to_vec(),
+            PendingBody::Chunked(decoder, _) => {
+                (request.body, request.trailers) = decoder.into_parts();
+            }
+        }
+
+        self.buffer.drain(..consumed);
+
+        Ok(ParseStatus::Complete { request, consumed })
+    }
+
+    /// Get the bytes that have been fed in but not yet consumed by a complete request.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Fail as soon as an incomplete head is over the limits, rather than buffering it until it
+    /// ends.
+    fn check_partial_head(&self) -> Result<(), Error> {
+        let request_line_err = Error {
+            err_type: ErrorType::RequestLineTooLong,
+            msg: format!(
+                "Request line longer than {} bytes",
+                self.config.max_request_line_len
+            ),
+        };
+
+        let head_err = Error {
+            err_type: ErrorType::HeadersTooLarge,
+            msg: format!("Headers longer than {} bytes", self.config.max_head_len),
+        };
+
+        let len = self.buffer.len();
+
+        // Leave room for the CRLF ending the request line, and the blank line ending the head.
+        match self.buffer.windows(2).position(|x| x == b"\r\n") {
+            None if len > self.config.max_request_line_len + 1 => Err(request_line_err),
+            Some(end) if len - end - 2 > self.config.max_head_len + 2 => Err(head_err),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::ErrorType;
     use crate::Header;
     use crate::Method;
+    use crate::ParserConfig;
     use crate::Request;
+    use crate::{ParseStatus, RequestParser};
 
     #[test]
     fn parse_get_request() {
@@ -224,7 +713,7 @@ mod tests {
 
     #[test]
     fn parse_post_request() {
-        let req_string = "POST /hello-world HTTP/1.1\r\nHost: localhost:3333\r\nUser-Agent: curl/7.81.0\r\nAccept: */*\r\nContent-Type: application/json\r\nContent-Length: 18\r\n\r\n{ \"hello\": \"world\" }";
+        let req_string = "POST /hello-world HTTP/1.1\r\nHost: localhost:3333\r\nUser-Agent: curl/7.81.0\r\nAccept: */*\r\nContent-Type: application/json\r\nContent-Length: 20\r\n\r\n{ \"hello\": \"world\" }";
         let result = Request::from_string(req_string).unwrap();
 
         assert_eq!(result.method, Method::POST);
@@ -239,8 +728,48 @@ mod tests {
             result.headers[3],
             Header::new("Content-Type", "application/json")
         );
-        assert_eq!(result.headers[4], Header::new("Content-Length", "18"));
-        assert_eq!(result.body, "{ \"hello\": \"world\" }");
+        assert_eq!(result.headers[4], Header::new("Content-Length", "20"));
+        assert_eq!(result.body_str(), Ok("{ \"hello\": \"world\" }"));
+    }
+
+    #[test]
+    fn parse_request_with_binary_body() {
+        let mut req_bytes =
+            b"POST /upload HTTP/1.1\r\nContent-Type: image/png\r\nContent-Length: 4\r\n\r\n"
+                .to_vec();
+        req_bytes.extend_from_slice(&[0x89, 0x50, 0x0d, 0xff]);
+        let result = Request::from_bytes(&req_bytes).unwrap();
+
+        assert_eq!(result.method, Method::POST);
+        assert_eq!(result.headers[0], Header::new("Content-Type", "image/png"));
+        assert_eq!(result.body, vec![0x89, 0x50, 0x0d, 0xff]);
+        assert!(result.body_str().is_err());
+    }
+
+    #[test]
+    fn parse_request_preserves_line_breaks_in_body() {
+        let req_string = "POST /upload HTTP/1.1\r\nContent-Type: text/csv\r\nContent-Length: 16\r\n\r\nname,age\r\nfoo,1\n";
+        let result = Request::from_string(req_string).unwrap();
+
+        assert_eq!(result.body_str(), Ok("name,age\r\nfoo,1\n"));
+    }
+
+    #[test]
+    fn parse_request_keeps_remainder() {
+        let req_string = "POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET / HTTP/1.1\r\n\r\n";
+        let (result, remainder) =
+            Request::from_bytes_with_remainder(req_string.as_bytes()).unwrap();
+
+        assert_eq!(result.body, b"hello");
+        assert_eq!(remainder, b"GET / HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    fn parse_request_with_truncated_body() {
+        let req_string = "POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello";
+        let result = Request::from_string(req_string);
+
+        assert!(result.is_err());
     }
 
     #[test]
@@ -265,4 +794,514 @@ mod tests {
         assert_eq!(result.path.uri, "/hello-world");
         assert_eq!(result.path.query.len(), 0);
     }
+
+    #[test]
+    fn incremental_parse_waits_for_complete_message() {
+        let mut parser = RequestParser::new();
+
+        let result = parser.feed(b"POST /hello HTTP/1.1\r\nContent-Le").unwrap();
+        assert_eq!(result, ParseStatus::Partial);
+
+        let result = parser.feed(b"ngth: 5\r\n\r\nhel").unwrap();
+        assert_eq!(result, ParseStatus::Partial);
+
+        let result = parser.feed(b"lo").unwrap();
+        match result {
+            ParseStatus::Complete { request, consumed } => {
+                assert_eq!(request.path.uri, "/hello");
+                assert_eq!(request.body, b"hello");
+                assert_eq!(consumed, 48);
+            }
+            ParseStatus::Partial => panic!("Expected a complete request"),
+        }
+    }
+
+    #[test]
+    fn incremental_parse_pipelined_requests() {
+        let mut parser = RequestParser::new();
+
+        let result = parser
+            .feed(b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n\r\n")
+            .unwrap();
+        match result {
+            ParseStatus::Complete { request, consumed } => {
+                assert_eq!(request.path.uri, "/a");
+                assert_eq!(consumed, 19);
+            }
+            ParseStatus::Partial => panic!("Expected a complete request"),
+        }
+
+        let result = parser.feed(&[]).unwrap();
+        match result {
+            ParseStatus::Complete { request, .. } => assert_eq!(request.path.uri, "/b"),
+            ParseStatus::Partial => panic!("Expected a complete request"),
+        }
+
+        assert!(parser.buffered().is_empty());
+    }
+
+    #[test]
+    fn parse_chunked_request() {
+        let req_string = "POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nChecksum: abc\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap();
+
+        assert_eq!(result.body_str(), Ok("hello world"));
+        assert_eq!(result.trailers.get("checksum"), Some("abc"));
+    }
+
+    #[test]
+    fn incremental_parse_chunked_request() {
+        let mut parser = RequestParser::new();
+
+        let result = parser
+            .feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel")
+            .unwrap();
+        assert_eq!(result, ParseStatus::Partial);
+
+        let result = parser
+            .feed(b"lo\r\n0\r\n\r\nGET / HTTP/1.1\r\n\r\n")
+            .unwrap();
+        match result {
+            ParseStatus::Complete { request, .. } => assert_eq!(request.body, b"hello"),
+            ParseStatus::Partial => panic!("Expected a complete request"),
+        }
+
+        assert_eq!(parser.buffered(), b"GET / HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    fn parse_request_with_encoded_query() {
+        let req_string = "GET /search?q=hello%20world&tag=a+b&tag=c HTTP/1.1\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap();
+
+        assert_eq!(result.path.query_get("q"), Some("hello world"));
+        assert_eq!(result.path.query_all("tag"), vec!["a b", "c"]);
+        assert_eq!(result.path.query_get("missing"), None);
+    }
+
+    #[test]
+    fn path_segments() {
+        let req_string = "GET /users/john%20doe/posts HTTP/1.1\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap();
+        let segments: Vec<String> = result.path.segments().collect();
+
+        assert_eq!(segments, vec!["users", "john doe", "posts"]);
+    }
+
+    #[test]
+    fn strict_parse_rejects_malformed_header() {
+        let req_string = "GET / HTTP/1.1\r\nHost: localhost\r\nnot a header\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap_err();
+
+        assert_eq!(
+            result.msg,
+            "Invalid header format at line 3, byte 33".to_string()
+        );
+    }
+
+    #[test]
+    fn error_offsets_count_message_bytes() {
+        let result =
+            Request::from_bytes(b"GET / HTTP/1.1\r\nX: \xe9\xe9\xe9\r\nnot a header\r\n\r\n");
+
+        assert_eq!(
+            result.unwrap_err().msg,
+            "Invalid header format at line 3, byte 24".to_string()
+        );
+
+        let result = Request::from_bytes(b"GET / HTTP/1.1\r\nX: \xe9\x01\r\n\r\n");
+
+        assert_eq!(
+            result.unwrap_err().msg,
+            "Control character in message head at line 2, byte 20".to_string()
+        );
+    }
+
+    #[test]
+    fn strict_parse_rejects_malformed_query() {
+        let req_string = "GET /search?q=ok&bad=%zz HTTP/1.1\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap_err();
+
+        assert!(result.msg.ends_with("at line 1, byte 17"));
+    }
+
+    #[test]
+    fn strict_parse_rejects_malformed_start_line() {
+        assert!(Request::from_string("GET  / HTTP/1.1\r\n\r\n").is_err());
+        assert!(Request::from_string("GET / HTTP/1.1 extra\r\n\r\n").is_err());
+        assert!(Request::from_string("GET / FOO/1\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn fragment_in_request_target() {
+        assert!(Request::from_string("GET /a#b HTTP/1.1\r\n\r\n").is_err());
+
+        let result =
+            Request::from_bytes_with_config(b"GET /a#b HTTP/1.1\r\n\r\n", &ParserConfig::lenient())
+                .unwrap();
+        assert_eq!(result.target.fragment, Some("b".to_string()));
+
+        let result = Request::builder(Method::GET, "/a#b").build().unwrap();
+        assert_eq!(result.target.fragment, Some("b".to_string()));
+        assert!(result.to_bytes().starts_with(b"GET /a HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn strict_parse_rejects_other_major_versions() {
+        let result = Request::from_string("GET / HTTP/2.0\r\n\r\n").unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::UnsupportedVersion);
+        assert_eq!(result.err_type.status().code(), 505);
+        assert!(Request::from_string("GET / HTTP/1.0\r\n\r\n").is_ok());
+    }
+
+    #[test]
+    fn lenient_parse_skips_malformed_lines() {
+        let req_string =
+            "GET /search?q=ok&bad=%zz HTTP/1.1\r\nnot a header\r\nHost: localhost\r\n\r\n";
+        let result =
+            Request::from_bytes_with_config(req_string.as_bytes(), &ParserConfig::lenient())
+                .unwrap();
+
+        assert_eq!(result.path.query.len(), 1);
+        assert_eq!(result.headers.len(), 1);
+        assert_eq!(result.headers.get("host"), Some("localhost"));
+    }
+
+    #[test]
+    fn parse_request_with_absolute_target() {
+        let req_string = "GET http://localhost:8080/hello?name=foo HTTP/1.1\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap();
+
+        assert_eq!(result.target.host, Some("localhost".to_string()));
+        assert_eq!(result.target.port, Some(8080));
+        assert_eq!(result.path.uri, "/hello");
+        assert_eq!(result.path.query_get("name"), Some("foo"));
+    }
+
+    #[test]
+    fn parse_connect_and_asterisk_requests() {
+        let result = Request::from_string("CONNECT example.com:443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(
+            result.target.authority(),
+            Some("example.com:443".to_string())
+        );
+
+        let result = Request::from_string("OPTIONS * HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(result.path.uri, "*");
+    }
+
+    #[test]
+    fn build_and_serialize_request() {
+        let result = Request::builder(Method::POST, "http://localhost:8080/search?page=2")
+            .query("q", "hello world")
+            .header(Header::new("Content-Type", "text/plain"))
+            .body("hi")
+            .build()
+            .unwrap()
+            .to_string();
+        let expected = "POST http://localhost:8080/search?page=2&q=hello%20world HTTP/1.1\r\nContent-Type: text/plain\r\nHost: localhost:8080\r\nContent-Length: 2\r\n\r\nhi";
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn serialized_request_round_trips() {
+        let request = Request::builder(Method::PUT, "/files/report%201.csv")
+            .query("tag", "a+b")
+            .query("tag", "c&d")
+            .header(Header::new("Host", "localhost"))
+            .body(b"name,age\r\nfoo,1\r\n")
+            .build()
+            .unwrap();
+        let result = Request::from_bytes(&request.to_bytes()).unwrap();
+
+        assert_eq!(result.path.query_all("tag"), vec!["a+b", "c&d"]);
+        assert_eq!(result, request);
+    }
+
+    #[test]
+    fn header_with_invalid_name() {
+        let result = Request::builder(Method::GET, "/")
+            .header(Header::new("Bad Name", "a"))
+            .build()
+            .unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidHeader);
+
+        let mut request = Request::builder(Method::GET, "/").build().unwrap();
+        request.headers.append(Header::new("X\r\nEvil", "a"));
+
+        assert_eq!(request.to_bytes(), b"GET / HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    fn non_ascii_header_round_trips() {
+        let request = Request::builder(Method::GET, "/")
+            .header(Header::new("X-Name", "José"))
+            .build()
+            .unwrap();
+        let bytes = request.to_bytes();
+        let result = Request::from_bytes(&bytes).unwrap();
+
+        assert!(bytes.windows(5).any(|x| x == b"Jos\xe9\r"));
+        assert_eq!(result.headers.get("X-Name"), Some("José"));
+        assert_eq!(result, request);
+    }
+
+    #[test]
+    fn serialized_chunked_request_round_trips() {
+        let mut request = Request::builder(Method::POST, "/upload")
+            .header(Header::new("Transfer-Encoding", "chunked"))
+            .body("hello")
+            .build()
+            .unwrap();
+        request.trailers.append(Header::new("Checksum", "abc"));
+        let result = Request::from_bytes(&request.to_bytes()).unwrap();
+
+        assert_eq!(result, request);
+    }
+
+    #[test]
+    fn parse_request_cookies() {
+        let req_string = "GET / HTTP/1.1\r\nCookie: session=abc123; theme=dark\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap().cookies();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name(), "session");
+        assert_eq!(result[0].value(), "abc123");
+        assert_eq!(result[1].name(), "theme");
+    }
+
+    #[test]
+    fn parse_form_body() {
+        let req_string = "POST /signup HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded; charset=utf-8\r\nContent-Length: 43\r\n\r\nname=John+Doe&email=john%40example.com&tag=";
+        let result = Request::from_string(req_string).unwrap().form().unwrap();
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].key(), "name");
+        assert_eq!(result[0].value(), "John Doe");
+        assert_eq!(result[1].value(), "john@example.com");
+        assert_eq!(result[2].value(), "");
+    }
+
+    #[test]
+    fn parse_form_body_with_wrong_content_type() {
+        let req_string =
+            "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 3\r\n\r\na=b";
+        let result = Request::from_string(req_string)
+            .unwrap()
+            .form()
+            .unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidContentType);
+    }
+
+    #[test]
+    fn parse_form_body_with_invalid_encoding() {
+        let req_string = "POST / HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: 5\r\n\r\na=%zz";
+        let result = Request::from_string(req_string)
+            .unwrap()
+            .form()
+            .unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidEncoding);
+    }
+
+    #[test]
+    fn parse_multipart_body() {
+        let req = Request::builder(Method::POST, "/upload")
+            .header(Header::new(
+                "Content-Type",
+                "multipart/form-data; boundary=abc",
+            ))
+            .body("--abc\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--abc--\r\n")
+            .build()
+            .unwrap();
+        let mut reader = req.multipart().unwrap();
+
+        assert_eq!(reader.next_part().unwrap().unwrap().name(), Some("a"));
+        assert_eq!(reader.data().unwrap(), b"1");
+        assert_eq!(reader.next_part().unwrap(), None);
+
+        let req = Request::builder(Method::POST, "/upload").build().unwrap();
+
+        assert_eq!(
+            req.multipart().unwrap_err().err_type,
+            ErrorType::InvalidContentType
+        );
+    }
+
+    #[test]
+    fn negotiate_response_type() {
+        let req_string = "GET / HTTP/1.1\r\nAccept: text/html;q=0.9, application/json\r\n\r\n";
+        let result = Request::from_string(req_string)
+            .unwrap()
+            .negotiate(&["text/html", "application/json"]);
+
+        assert_eq!(result.choice(), Some("application/json"));
+    }
+
+    #[test]
+    fn reject_smuggling_framing() {
+        let cases = [
+            (
+                "POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+                ErrorType::InvalidFraming,
+            ),
+            (
+                "POST / HTTP/1.1\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\nabcd",
+                ErrorType::InvalidContentLength,
+            ),
+            (
+                "POST / HTTP/1.1\r\nContent-Length: +3\r\n\r\nabc",
+                ErrorType::InvalidContentLength,
+            ),
+            (
+                "POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n",
+                ErrorType::InvalidTransferEncoding,
+            ),
+            (
+                "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n\r\n",
+                ErrorType::InvalidTransferEncoding,
+            ),
+            (
+                "GET / HTTP/1.1\r\nHost : localhost\r\n\r\n",
+                ErrorType::InvalidHeaderName,
+            ),
+            (
+                "GET / HTTP/1.1\r\nHost: localhost\r\n folded\r\n\r\n",
+                ErrorType::ObsoleteLineFolding,
+            ),
+            (
+                "GET / HTTP/1.1\r\nHost: local\rhost\r\n\r\n",
+                ErrorType::BareCarriageReturn,
+            ),
+            (
+                "GET / HTTP/1.1\r\nHost: a\nX-Evil: b\r\n\r\n",
+                ErrorType::ControlCharacter,
+            ),
+            (
+                "GET / HTTP/1.1\r\nX: a\x01b\0\r\n\r\n",
+                ErrorType::ControlCharacter,
+            ),
+            (
+                "POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+                ErrorType::InvalidFraming,
+            ),
+        ];
+
+        for (req_string, err_type) in cases {
+            let result = Request::from_string(req_string).unwrap_err();
+
+            assert_eq!(result.err_type, err_type, "{}", req_string);
+        }
+    }
+
+    #[test]
+    fn normalise_smuggling_hazards() {
+        let req_string = "POST / HTTP/1.1\r\nContent-Length: 3, 3\r\nContent-Length: 3\r\nX-Long: a\r\n\tb\r\nX-Cr: a\rb\r\nX-Ctl: a\nb\0\r\n\r\nabc";
+        let result =
+            Request::from_bytes_with_config(req_string.as_bytes(), &ParserConfig::lenient())
+                .unwrap();
+
+        assert_eq!(result.headers.get_all("Content-Length"), vec!["3"]);
+        assert_eq!(result.headers.get("X-Long"), Some("a b"));
+        assert_eq!(result.headers.get("X-Cr"), Some("a b"));
+        assert_eq!(result.headers.get("X-Ctl"), Some("a b"));
+        assert_eq!(result.body, b"abc");
+
+        let result = Request::from_bytes_with_config(
+            b"GET / HTTP/1.1\r\nHost : localhost\r\n\r\n",
+            &ParserConfig::lenient(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parser_limits() {
+        let config = ParserConfig {
+            max_request_line_len: 40,
+            max_uri_len: 20,
+            max_headers: 2,
+            max_header_len: 20,
+            max_head_len: 30,
+            max_body_len: 4,
+            ..ParserConfig::default()
+        };
+
+        let cases = [
+            (
+                format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(40)),
+                ErrorType::RequestLineTooLong,
+                414,
+            ),
+            (
+                format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(20)),
+                ErrorType::UriTooLong,
+                414,
+            ),
+            (
+                "GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n".to_string(),
+                ErrorType::TooManyHeaders,
+                431,
+            ),
+            (
+                format!("GET / HTTP/1.1\r\nA: {}\r\n\r\n", "a".repeat(20)),
+                ErrorType::HeaderTooLarge,
+                431,
+            ),
+            (
+                format!("GET / HTTP/1.1\r\nA: {0}\r\nB: {0}\r\n\r\n", "a".repeat(15)),
+                ErrorType::HeadersTooLarge,
+                431,
+            ),
+            (
+                "POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello".to_string(),
+                ErrorType::ContentTooLarge,
+                413,
+            ),
+        ];
+
+        for (req_string, err_type, code) in cases {
+            let result =
+                Request::from_bytes_with_config(req_string.as_bytes(), &config).unwrap_err();
+
+            assert_eq!(result.err_type, err_type, "{}", req_string);
+            assert_eq!(result.err_type.status().code(), code);
+        }
+
+        let req_string = "POST / HTTP/1.1\r\nContent-Length: 99999999999999999999999\r\n\r\n";
+        let result = Request::from_string(req_string).unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ContentTooLarge);
+
+        let mut parser = RequestParser::new();
+        let result = parser.feed(req_string.as_bytes()).unwrap_err();
+
+        assert_eq!(result.err_type.status().code(), 413);
+    }
+
+    #[test]
+    fn request_parser_limits() {
+        let config = ParserConfig {
+            max_request_line_len: 16,
+            max_body_len: 4,
+            ..ParserConfig::default()
+        };
+
+        let mut parser = RequestParser::with_config(config.clone());
+        let result = parser.feed(b"GET /aaaaaaaaaaaaaaaa").unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::RequestLineTooLong);
+
+        let mut parser = RequestParser::with_config(config);
+        parser
+            .feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n")
+            .unwrap();
+        let result = parser.feed(b"5\r\nhello\r\n").unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ContentTooLarge);
+    }
 }
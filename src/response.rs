@@ -1,4 +1,19 @@
-use crate::header::Header;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::from_utf8;
+
+use crate::chunked;
+use crate::conditional::ETag;
+use crate::config::ParserConfig;
+use crate::cookie::SetCookie;
+use crate::date::HttpDate;
+use crate::error::{Error, ErrorType};
+use crate::header::{Header, HeaderMap};
+use crate::message::{self, Framing};
+use crate::method::Method;
+use crate::range;
+use crate::request::Request;
 use crate::status::Status;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -6,8 +21,9 @@ pub struct Response {
     scheme: String,
     version: String,
     status: Status,
-    headers: Vec<Header>,
-    content: String,
+    headers: HeaderMap,
+    content: Vec<u8>,
+    trailers: HeaderMap,
 }
 
 impl Response {
@@ -17,8 +33,9 @@ impl Response {
             scheme: "HTTP".to_string(),
             version: "1.1".to_string(),
             status: Status::Ok,
-            headers: Vec::new(),
-            content: String::new(),
+            headers: HeaderMap::new(),
+            content: Vec::new(),
+            trailers: HeaderMap::new(),
         }
     }
 
@@ -29,8 +46,9 @@ impl Response {
             scheme: "HTTP".to_string(),
             version: "1.1".to_string(),
             status: Status::Ok,
-            headers: Vec::new(),
-            content: content.to_string(),
+            headers: HeaderMap::new(),
+            content: content.as_bytes().to_vec(),
+            trailers: HeaderMap::new(),
         }
     }
 
@@ -45,6 +63,20 @@ impl Response {
             .header(Header::new("Content-Length", &content_length.to_string()))
     }
 
+    /// Create a new http response with a binary body, e.g. an image. Sets the `Content-Type`
+    /// header to the content type provided, and the `Content-Length` header to the number of
+    /// bytes in the body.
+    pub fn bytes(content: Vec<u8>, content_type: &str) -> Self {
+        let content_length = content.len();
+
+        Response {
+            content,
+            ..Self::empty()
+        }
+        .header(Header::new("Content-Type", content_type))
+        .header(Header::new("Content-Length", &content_length.to_string()))
+    }
+
     /// Create a `html` http response. This method is the same as [Response::content], but it
     /// automatically sets the `Content-Type: text/html` header.
     pub fn html(content: &str) -> Self {
@@ -57,9 +89,85 @@ impl Response {
         Self::content(content, "application/json")
     }
 
-    /// Add a cookie to the http response.
-    pub fn cookie(self, content: &str) -> Self {
-        self.header(Header::new("Set-Cookie", content))
+    /// Create a http response with a binary body, honouring the `Range` header of the request.
+    /// Responds with `206 Partial Content` when a satisfiable range is requested, using a
+    /// `multipart/byteranges` body for more than one range, or `416 Range Not Satisfiable` when
+    /// none of the ranges can be satisfied. Otherwise the whole body is sent, like
+    /// [Response::bytes].
+    ///
+    /// The `validator` is the `ETag` or `Last-Modified` date of the body, to check against the
+    /// `If-Range` header. Without a validator, a request with `If-Range` gets the whole body.
+    ///
+    /// Overlapping and adjacent ranges are merged, and a request for more than 16 ranges gets the
+    /// whole body.
+    pub fn partial(
+        request: &Request,
+        content: Vec<u8>,
+        content_type: &str,
+        validator: Option<&str>,
+    ) -> Self {
+        let len = content.len() as u64;
+
+        // An invalid `Range` header is ignored, as are ranges on anything but `GET`.
+        let ranges = match request.ranges() {
+            Ok(ranges) if request.method == Method::GET => ranges,
+            _ => Vec::new(),
+        };
+
+        let if_range = match validator {
+            Some(validator) => request.if_range(validator),
+            None => !request.headers.contains("If-Range"),
+        };
+
+        // Sending the whole representation is allowed for any range request, which is the safest
+        // response to one asking for more ranges than is reasonable.
+        if ranges.is_empty() || ranges.len() > range::MAX_RANGES || !if_range {
+            return Self::bytes(content, content_type)
+                .header(Header::new("Accept-Ranges", "bytes"));
+        }
+
+        let ranges = range::coalesce(ranges.iter().flat_map(|x| x.resolve(len)).collect());
+
+        match ranges[..] {
+            [] => Self::empty()
+                .status(Status::RangeNotSatisfiable)
+                .header(Header::new("Content-Range", &format!("bytes */{}", len)))
+                .header(Header::new("Content-Length", "0")),
+            [(first, last)] => {
+                let content_range = format!("bytes {}-{}/{}", first, last, len);
+
+                Self::bytes(
+                    content[first as usize..=last as usize].to_vec(),
+                    content_type,
+                )
+                .status(Status::PartialContent)
+                .header(Header::new("Content-Range", &content_range))
+                .header(Header::new("Accept-Ranges", "bytes"))
+            }
+            _ => {
+                let (body, boundary) = range::encode_byteranges(&content, content_type, &ranges);
+                let content_type = format!("multipart/byteranges; boundary={}", boundary);
+
+                Self::bytes(body, &content_type)
+                    .status(Status::PartialContent)
+                    .header(Header::new("Accept-Ranges", "bytes"))
+            }
+        }
+    }
+
+    /// Add a cookie to the http response, as a `Set-Cookie` header.
+    pub fn cookie(self, cookie: SetCookie) -> Self {
+        self.header(Header::new("Set-Cookie", &cookie.to_string()))
+    }
+
+    /// Set the `ETag` header of the http response.
+    pub fn etag(self, etag: &ETag) -> Self {
+        self.replace_header(Header::new("ETag", &etag.to_string()))
+    }
+
+    /// Set the `Last-Modified` header of the http response, from a [HttpDate] or `SystemTime`.
+    pub fn last_modified(self, date: impl Into<HttpDate>) -> Self {
+        self.replace_header(Header::new("Last-Modified", &date.into().to_string()))
     }
 
     /// Set the status of the http response.
@@ -67,37 +175,422 @@ impl Response {
         Response { status, ..self }
     }
 
-    /// Add a header to the http response.
+    /// Add a header to the http response, replacing any existing headers with the same name. A
+    /// header with an invalid name is dropped, see [Response::header].
+    pub fn replace_header(self, header: Header) -> Self {
+        if !header.has_valid_name() {
+            return self;
+        }
+
+        let mut headers = self.headers;
+        headers.insert(header);
+
+        Response { headers, ..self }
+    }
+
+    /// Add a header to the http response, keeping any existing headers with the same name. Any
+    /// characters not allowed in the value were already replaced by [Header::new]. A header with
+    /// a name which isn't a valid token is dropped, see [Header::try_new] to reject it instead.
     pub fn header(self, header: Header) -> Self {
+        if !header.has_valid_name() {
+            return self;
+        }
+
         let mut headers = self.headers;
-        headers.push(header);
+        headers.append(header);
 
         Response { headers, ..self }
     }
-}
 
-impl ToString for Response {
-    /// Convert the `Response` to a valid http plaintext response.
-    fn to_string(&self) -> String {
-        let headers = self
-            .headers
+    /// Create a new http response with a chunked body, to be written with
+    /// [Response::write_chunked] or [Response::write_chunked_from]. Sets the `Transfer-Encoding:
+    /// chunked` header.
+    pub fn chunked() -> Self {
+        Self::empty().header(Header::new("Transfer-Encoding", "chunked"))
+    }
+
+    /// Add a trailer to the http response, sent after the last chunk of a chunked body. It is up to
+    /// the consumer of the library to announce trailers with the `Trailer` header. A trailer with
+    /// an invalid name is dropped, as for [Response::header].
+    pub fn trailer(self, trailer: Header) -> Self {
+        if !trailer.has_valid_name() {
+            return self;
+        }
+
+        let mut trailers = self.trailers;
+        trailers.append(trailer);
+
+        Response { trailers, ..self }
+    }
+
+    /// Write the http response to `writer` with a chunked body, where each item of `chunks` is
+    /// sent as a separate chunk. Any body set on the response is ignored. The `Transfer-Encoding:
+    /// chunked` header is added if it isn't already set, and any `Content-Length` header is
+    /// dropped. The body is ended with the last chunk and any trailers.
+    pub fn write_chunked<W, I>(&self, writer: &mut W, chunks: I) -> io::Result<()>
+    where
+        W: Write,
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        writer.write_all(&message::encode_latin1(&self.chunked_head()))?;
+
+        for chunk in chunks {
+            writer.write_all(&chunked::encode_chunk(chunk.as_ref()))?;
+        }
+
+        writer.write_all(&chunked::encode_last_chunk(&self.trailers))?;
+        writer.flush()
+    }
+
+    /// Write the http response to `writer` with a chunked body read from `reader`. Each read from
+    /// `reader` is sent as a separate chunk, until it is exhausted. See [Response::write_chunked].
+    pub fn write_chunked_from<W: Write, R: Read>(
+        &self,
+        writer: &mut W,
+        reader: &mut R,
+    ) -> io::Result<()> {
+        writer.write_all(&message::encode_latin1(&self.chunked_head()))?;
+
+        let mut buffer = [0; 8192];
+
+        loop {
+            let len = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+
+            writer.write_all(&chunked::encode_chunk(&buffer[..len]))?;
+        }
+
+        writer.write_all(&chunked::encode_last_chunk(&self.trailers))?;
+        writer.flush()
+    }
+
+    /// Parse a http plaintext response into a `Response` object.
+    pub fn from_string(buffer: &str) -> Result<Self, Error> {
+        Self::from_bytes(buffer.as_bytes())
+    }
+
+    /// Parse a raw http response into a `Response` object. The body is framed by the
+    /// `Content-Length` header, or decoded from `Transfer-Encoding: chunked`. If neither is set,
+    /// the body is the rest of the buffer. `1xx`, `204` and `304` responses never have a body.
+    ///
+    /// Parsing is strict, so any malformed line is an error.
+    pub fn from_bytes(buffer: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_config(buffer, &ParserConfig::default())
+    }
+
+    /// Parse a raw http response in the same way as [Response::from_bytes], using the given
+    /// config.
+    pub fn from_bytes_with_config(buffer: &[u8], config: &ParserConfig) -> Result<Self, Error> {
+        Self::parse(buffer, None, config)
+    }
+
+    /// Parse a raw http response to a request with the given method, in the same way as
+    /// [Response::from_bytes]. A response to `HEAD`, or a `2xx` response to `CONNECT`, never has
+    /// a body, whatever its framing headers say.
+    pub fn from_bytes_for(buffer: &[u8], method: &Method) -> Result<Self, Error> {
+        Self::parse(buffer, Some(method), &ParserConfig::default())
+    }
+
+    fn parse(buffer: &[u8], method: Option<&Method>, config: &ParserConfig) -> Result<Self, Error> {
+        let head_len = message::find_head_end(buffer).unwrap_or(buffer.len());
+        let head =
+            message::replace_control_chars(message::decode_latin1(&buffer[..head_len]), config)?;
+
+        let mut lines = head.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+
+        let (scheme, version, status) = Self::parse_status_line(status_line, config)?;
+        let mut headers =
+            message::parse_headers(lines, message::byte_len(status_line) + 2, config)?;
+        message::check_framing(&mut headers)?;
+
+        let framing = match (method, status.code()) {
+            (Some(Method::HEAD), _) | (Some(Method::CONNECT), 200..=299) => Framing::Length(0),
+            (_, 100..=199 | 204 | 304) => Framing::Length(0),
+            _ => message::framing(&headers)?.unwrap_or(Framing::Length(buffer.len() - head_len)),
+        };
+
+        let (body, trailers, _) = message::read_body(&buffer[head_len..], framing, config)?;
+
+        Ok(Response {
+            scheme: scheme.to_string(),
+            version: version.to_string(),
+            status,
+            headers,
+            content: body,
+            trailers,
+        })
+    }
+
+    /// Get the scheme of the http response, e.g. `HTTP`.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Get the version of the http response, e.g. `1.1`.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Get the status of the http response. Named so as not to clash with [Response::status].
+    pub fn get_status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Get the headers of the http response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get a mutable reference to the headers of the http response.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Get the trailers of the http response, sent after a chunked body.
+    pub fn trailers(&self) -> &HeaderMap {
+        &self.trailers
+    }
+
+    /// Get the body of the http response as raw bytes. Named so as not to clash with
+    /// [Response::body].
+    pub fn get_body(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Get the body of the http response as a string slice. Fails if the body is not valid UTF-8.
+    pub fn body_str(&self) -> Result<&str, Error> {
+        from_utf8(&self.content).map_err(|_| Error {
+            err_type: ErrorType::InvalidEncoding,
+            msg: "Response body is not valid UTF-8".to_string(),
+        })
+    }
+
+    /// Convert the `Response` to a valid raw http response. If the `Transfer-Encoding` header is
+    /// set to chunked, the body is sent as a single chunk followed by any trailers. The headers and
+    /// body are sent exactly as set, see [Response::to_bytes_for] to have them fixed up.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = message::encode_latin1(&self.head(&self.headers));
+        bytes.extend_from_slice(&self.encoded_body());
+
+        bytes
+    }
+
+    /// Prepare the response to be sent in reply to `request`, fixing up the headers and body
+    /// the way RFC 9110 requires:
+    ///
+    /// - A `Date` header is added if missing, except on `1xx` responses.
+    /// - A `Content-Length` header is added if the body isn't otherwise framed, unless the body
+    ///   given for a `HEAD` request is empty.
+    /// - The body is removed for responses to `HEAD`, and for `1xx`, `204` and `304` responses.
+    ///   `1xx` and `204` responses also lose any `Content-Length` and `Transfer-Encoding`.
+    ///
+    /// Fails if the framing headers contradict each other or the body, e.g. both
+    /// `Transfer-Encoding` and `Content-Length` are set.
+    pub fn finalize(self, request: Option<&Request>) -> Result<Self, Error> {
+        let conflict_err = Error {
+            err_type: ErrorType::InvalidFraming,
+            msg: "Response has both Transfer-Encoding and Content-Length headers".to_string(),
+        };
+
+        let length_err = Error {
+            err_type: ErrorType::InvalidFraming,
+            msg: "Content-Length header does not match the body".to_string(),
+        };
+
+        let mut headers = self.headers;
+        let code = self.status.code();
+        let transfer_encoding = headers.contains("Transfer-Encoding");
+
+        let lengths: Vec<&str> = headers
+            .get_all("Content-Length")
+            .into_iter()
+            .flat_map(|x| x.split(','))
+            .map(|x| x.trim())
+            .collect();
+
+        if transfer_encoding && !lengths.is_empty() {
+            return Err(conflict_err);
+        }
+
+        let length = match lengths.first() {
+            Some(first) if lengths.iter().any(|x| x != first) => return Err(length_err),
+            Some(first) if !message::is_length(first) => return Err(length_err),
+            Some(first) => Some(first.parse::<usize>().map_err(|_| length_err.clone())?),
+            None => None,
+        };
+
+        let head = request.is_some_and(|x| x.method == Method::HEAD);
+
+        // Only a response to `HEAD` and a `304 Not Modified` response can give the length of a
+        // body they don't send.
+        match length {
+            Some(_) if self.content.is_empty() && (head || code == 304) => (),
+            Some(length) if length != self.content.len() => return Err(length_err),
+            _ => (),
+        }
+
+        let no_body = code < 200 || code == 204 || code == 304;
+
+        if code >= 200 && !headers.contains("Date") {
+            headers.append(Header::new("Date", &HttpDate::now().to_string()));
+        }
+
+        if code < 200 || code == 204 {
+            headers.remove("Content-Length");
+            headers.remove("Transfer-Encoding");
+        } else if code == 304 {
+            headers.remove("Transfer-Encoding");
+        } else if !transfer_encoding && length.is_none() && !(head && self.content.is_empty()) {
+            // The length of an empty body given for `HEAD` isn't known, so it is left out rather
+            // than claiming the resource is empty.
+            let length = self.content.len().to_string();
+            headers.append(Header::new("Content-Length", &length));
+        } else if head {
+            // There is no chunked body to send, so `Transfer-Encoding` would be misleading.
+            headers.remove("Transfer-Encoding");
+        }
+
+        let (content, trailers) = if head || no_body {
+            (Vec::new(), HeaderMap::new())
+        } else {
+            (self.content, self.trailers)
+        };
+
+        Ok(Response {
+            headers,
+            content,
+            trailers,
+            ..self
+        })
+    }
+
+    /// Convert the `Response` to a raw http response to `request`, after applying
+    /// [Response::finalize].
+    pub fn to_bytes_for(&self, request: Option<&Request>) -> Result<Vec<u8>, Error> {
+        Ok(self.clone().finalize(request)?.to_bytes())
+    }
+
+    /// Write the http response to `writer`, in the same way as [Response::to_bytes].
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&message::encode_latin1(&self.head(&self.headers)))?;
+        writer.write_all(&self.encoded_body())?;
+        writer.flush()
+    }
+
+    fn head(&self, headers: &HeaderMap) -> String {
+        let headers = headers
             .iter()
+            .filter(|x| x.has_valid_name())
             .fold(String::new(), |a, b| a + &b.to_string() + "\r\n");
 
         format!(
-            "{}/{} {}\r\n{}\r\n{}",
-            self.scheme,
-            self.version,
-            self.status.to_string(),
-            headers,
-            self.content
+            "{}/{} {}\r\n{}\r\n",
+            self.scheme, self.version, self.status, headers
+        )
+    }
+
+    fn encoded_body(&self) -> Cow<'_, [u8]> {
+        if self.is_chunked() {
+            Cow::Owned(chunked::encode(&self.content, &self.trailers))
+        } else {
+            Cow::Borrowed(&self.content)
+        }
+    }
+
+    fn chunked_head(&self) -> String {
+        let mut headers = self.headers.clone();
+        headers.remove("Content-Length");
+
+        if !self.is_chunked() {
+            headers.insert(Header::new("Transfer-Encoding", "chunked"));
+        }
+
+        self.head(&headers)
+    }
+
+    fn is_chunked(&self) -> bool {
+        matches!(message::framing(&self.headers), Ok(Some(Framing::Chunked)))
+    }
+
+    fn parse_status_line<'a>(
+        line: &'a str,
+        config: &ParserConfig,
+    ) -> Result<(&'a str, &'a str, Status), Error> {
+        let parser_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid status line format".to_string(),
+        };
+
+        let code_err = Error {
+            err_type: ErrorType::InvalidCode,
+            msg: "Invalid http status code".to_string(),
+        };
+
+        let (protocol, rest) = match line.split_once(' ') {
+            Some(parts) => parts,
+            None => return Err(parser_err.at(1, 0)),
+        };
+
+        let (scheme, version) =
+            message::parse_protocol(protocol, config).map_err(|x| x.at(1, 0))?;
+
+        let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+
+        let code_offset = protocol.len() + 1;
+
+        if code.len() != 3 || !code.chars().all(|x| x.is_ascii_digit()) {
+            return Err(code_err.at(1, code_offset));
+        }
+
+        let status = match code.parse() {
+            Ok(code) => Status::from_code(code).map_err(|x| x.at(1, code_offset))?,
+            Err(_) => return Err(code_err.at(1, code_offset)),
+        };
+
+        // Keep the reason phrase for codes which aren't registered. Strict parsing rejects a reason
+        // with characters which aren't allowed, lenient parsing keeps it to be sanitised when
+        // written out.
+        let status = match status {
+            Status::Custom(code, _) if config.strict => {
+                let reason_offset = code_offset + 4;
+                Status::custom(code, reason).map_err(|x| x.at(1, reason_offset))?
+            }
+            Status::Custom(code, _) => Status::Custom(code, reason.to_string()),
+            status => status,
+        };
+
+        Ok((scheme, version, status))
+    }
+}
+
+impl fmt::Display for Response {
+    /// Convert the `Response` to a valid http plaintext response. A body which isn't valid UTF-8
+    /// is converted lossily, see [Response::to_bytes] to keep it intact.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let body = self.encoded_body();
+
+        write!(
+            f,
+            "{}{}",
+            self.head(&self.headers),
+            String::from_utf8_lossy(&body)
         )
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::ErrorType;
     use crate::Header;
+    use crate::Method;
+    use crate::MultipartReader;
+    use crate::Request;
     use crate::Response;
     use crate::Status;
 
@@ -142,7 +635,7 @@ mod tests {
     fn set_status() {
         let result = Response::empty().status(Status::Forbidden);
 
-        assert!(result.to_string().contains("403 FORBIDDEN"));
+        assert!(result.to_string().contains("403 Forbidden"));
     }
 
     #[test]
@@ -163,14 +656,372 @@ mod tests {
         assert!(result.to_string().contains("Content-Length: 89"));
     }
 
+    #[test]
+    fn replace_header() {
+        let result = Response::empty()
+            .header(Header::new("Cache-Control", "no-cache"))
+            .replace_header(Header::new("cache-control", "no-store"));
+
+        assert_eq!(result.headers().get_all("Cache-Control"), vec!["no-store"]);
+    }
+
     #[test]
     fn response_format() {
         let html = "<html><head><title>Hello, world!</title></head><body><h1>Hello, world!</h1></body></html>";
         let result = Response::content(html, "text/html")
             .status(Status::SeeOther)
             .to_string();
-        let expected = "HTTP/1.1 303 SEE OTHER\r\nContent-Type: text/html\r\nContent-Length: 89\r\n\r\n<html><head><title>Hello, world!</title></head><body><h1>Hello, world!</h1></body></html>";
+        let expected = "HTTP/1.1 303 See Other\r\nContent-Type: text/html\r\nContent-Length: 89\r\n\r\n<html><head><title>Hello, world!</title></head><body><h1>Hello, world!</h1></body></html>";
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn parse_response() {
+        let res_string = "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: 11\r\n\r\n{\"id\": 123}";
+        let result = Response::from_string(res_string).unwrap();
+
+        assert_eq!(result.scheme(), "HTTP");
+        assert_eq!(result.version(), "1.1");
+        assert_eq!(result.get_status(), &Status::Created);
+        assert_eq!(
+            result.headers().get("content-type"),
+            Some("application/json")
+        );
+        assert_eq!(result.body_str(), Ok("{\"id\": 123}"));
+    }
+
+    #[test]
+    fn parse_chunked_response() {
+        let res_string = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\nExpires: never\r\n\r\n";
+        let result = Response::from_string(res_string).unwrap();
+
+        assert_eq!(result.get_body(), b"hello");
+        assert_eq!(result.trailers().get("Expires"), Some("never"));
+    }
+
+    #[test]
+    fn parse_response_with_invalid_framing() {
+        let res_string =
+            "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+        let result = Response::from_string(res_string).unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidFraming);
+
+        let res_string = "HTTP/1.1 200 OK\r\nContent-Length: +3\r\n\r\nabc";
+        let result = Response::from_string(res_string).unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidContentLength);
+    }
+
+    #[test]
+    fn parse_response_with_invalid_reason() {
+        let result = Response::from_string("HTTP/1.1 299 a\x01b\r\n\r\n").unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::ControlCharacter);
+
+        let result = Response::from_string("HTTP/1.1 2990 Whatever\r\n\r\n").unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidCode);
+    }
+
+    #[test]
+    fn parse_response_without_framing() {
+        let res_string = "HTTP/1.0 299 Whatever\r\n\r\nread until close";
+        let result = Response::from_string(res_string).unwrap();
+
+        assert_eq!(
+            result.get_status(),
+            &Status::Custom(299, "Whatever".to_string())
+        );
+        assert_eq!(result.body_str(), Ok("read until close"));
+    }
+
+    #[test]
+    fn parsed_response_round_trips() {
+        let response = Response::json("{}").status(Status::Accepted);
+        let result = Response::from_string(&response.to_string()).unwrap();
+
+        assert_eq!(result, response);
+    }
+
+    #[test]
+    fn header_with_invalid_name() {
+        let result = Response::body("Hello")
+            .header(Header::new("X\r\nSet-Cookie", "a"))
+            .replace_header(Header::new("Bad Name", "b"))
+            .trailer(Header::new("", "c"));
+
+        assert!(result.headers().iter().all(|x| !x.name().contains(' ')));
+        assert!(!result.headers().contains("X\r\nSet-Cookie"));
+        assert!(result.trailers().is_empty());
+    }
+
+    #[test]
+    fn parse_response_for_method() {
+        let res_string = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n";
+        assert!(Response::from_string(res_string).is_err());
+
+        let result = Response::from_bytes_for(res_string.as_bytes(), &Method::HEAD).unwrap();
+        assert_eq!(result.headers().get("Content-Length"), Some("10"));
+        assert!(result.get_body().is_empty());
+
+        let res_string = "HTTP/1.1 200 Connection Established\r\n\r\ntunnelled bytes";
+        let result = Response::from_bytes_for(res_string.as_bytes(), &Method::CONNECT).unwrap();
+        assert!(result.get_body().is_empty());
+
+        let result = Response::from_bytes_for(res_string.as_bytes(), &Method::GET).unwrap();
+        assert_eq!(result.get_body(), b"tunnelled bytes");
+    }
+
+    #[test]
+    fn non_ascii_header_round_trips() {
+        let raw = b"HTTP/1.1 200 OK\r\nX-Name: Jos\xe9\r\nContent-Length: 2\r\n\r\nhi";
+        let result = Response::from_bytes(raw).unwrap();
+
+        assert_eq!(result.headers().get("X-Name"), Some("José"));
+        assert_eq!(result.to_bytes(), raw);
+    }
+
+    #[test]
+    fn parse_invalid_status_line() {
+        assert!(Response::from_string("HTTP/1.1 20 OK\r\n\r\n").is_err());
+        assert!(Response::from_string("HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn write_chunked_response() {
+        let mut result = Vec::new();
+        Response::chunked()
+            .header(Header::new("Trailer", "Checksum"))
+            .trailer(Header::new("Checksum", "abc"))
+            .write_chunked(&mut result, ["hello", ", world", ""])
+            .unwrap();
+        let expected = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: Checksum\r\n\r\n5\r\nhello\r\n7\r\n, world\r\n0\r\nChecksum: abc\r\n\r\n";
+
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_chunked_response_from_reader() {
+        let mut result = Vec::new();
+        Response::content("ignored", "text/csv")
+            .write_chunked_from(&mut result, &mut "a,b\n1,2\n".as_bytes())
+            .unwrap();
+        let expected = "HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\nTransfer-Encoding: chunked\r\n\r\n8\r\na,b\n1,2\n\r\n0\r\n\r\n";
+
+        assert_eq!(String::from_utf8(result).unwrap(), expected);
+    }
+
+    #[test]
+    fn chunked_response_round_trips() {
+        let response = Response::chunked()
+            .trailer(Header::new("Expires", "never"))
+            .header(Header::new("Content-Type", "text/plain"));
+        let mut buffer = Vec::new();
+        response.write_chunked(&mut buffer, ["a", "b"]).unwrap();
+        let result = Response::from_bytes(&buffer).unwrap();
+
+        assert_eq!(result.get_body(), b"ab");
+        assert_eq!(result.trailers(), response.trailers());
+    }
+
+    #[test]
+    fn binary_response() {
+        let png = vec![0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0xff];
+        let result = Response::bytes(png.clone(), "image/png");
+
+        let mut expected =
+            b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 9\r\n\r\n".to_vec();
+        expected.extend_from_slice(&png);
+
+        assert_eq!(result.to_bytes(), expected);
+        assert!(result.body_str().is_err());
+
+        let mut written = Vec::new();
+        result.write_to(&mut written).unwrap();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn content_length_counts_bytes() {
+        let result = Response::content("héllo", "text/plain");
+
+        assert_eq!(result.headers().get("Content-Length"), Some("6"));
+    }
+
+    #[test]
+    fn partial_response_single_range() {
+        let req = Request::builder(Method::GET, "/video")
+            .header(Header::new("Range", "bytes=-3"))
+            .build()
+            .unwrap();
+        let result = Response::partial(&req, b"0123456789".to_vec(), "video/mp4", None);
+
+        assert_eq!(result.get_status(), &Status::PartialContent);
+        assert_eq!(result.headers().get("Content-Range"), Some("bytes 7-9/10"));
+        assert_eq!(result.get_body(), b"789");
+    }
+
+    #[test]
+    fn partial_response_multiple_ranges() {
+        let req = Request::builder(Method::GET, "/video")
+            .header(Header::new("Range", "bytes=0-1, 5-"))
+            .build()
+            .unwrap();
+        let result = Response::partial(&req, b"0123456789".to_vec(), "video/mp4", None);
+        let content_type = result.headers().get("Content-Type").unwrap();
+        let mut reader =
+            MultipartReader::from_content_type(result.get_body(), content_type).unwrap();
+
+        let part = reader.next_part().unwrap().unwrap();
+        assert_eq!(part.headers().get("Content-Range"), Some("bytes 0-1/10"));
+        assert_eq!(reader.data().unwrap(), b"01");
+
+        let part = reader.next_part().unwrap().unwrap();
+        assert_eq!(part.headers().get("Content-Range"), Some("bytes 5-9/10"));
+        assert_eq!(reader.data().unwrap(), b"56789");
+
+        assert_eq!(reader.next_part().unwrap(), None);
+    }
+
+    #[test]
+    fn partial_response_coalesces_ranges() {
+        let req = Request::builder(Method::GET, "/video")
+            .header(Header::new("Range", "bytes=4-6, 0-2, 3-4"))
+            .build()
+            .unwrap();
+        let result = Response::partial(&req, b"0123456789".to_vec(), "video/mp4", None);
+
+        assert_eq!(result.get_status(), &Status::PartialContent);
+        assert_eq!(result.headers().get("Content-Range"), Some("bytes 0-6/10"));
+        assert_eq!(result.get_body(), b"0123456");
+
+        let range = format!("bytes={}", vec!["0-"; 1000].join(","));
+        let req = Request::builder(Method::GET, "/video")
+            .header(Header::new("Range", &range))
+            .build()
+            .unwrap();
+        let result = Response::partial(&req, b"0123456789".to_vec(), "video/mp4", None);
+
+        assert_eq!(result.get_status(), &Status::Ok);
+        assert_eq!(result.get_body(), b"0123456789");
+    }
+
+    #[test]
+    fn partial_response_unsatisfiable_range() {
+        let req = Request::builder(Method::GET, "/video")
+            .header(Header::new("Range", "bytes=20-"))
+            .build()
+            .unwrap();
+        let result = Response::partial(&req, b"0123456789".to_vec(), "video/mp4", None);
+
+        assert_eq!(result.get_status(), &Status::RangeNotSatisfiable);
+        assert_eq!(result.headers().get("Content-Range"), Some("bytes */10"));
+        assert!(result.get_body().is_empty());
+    }
+
+    #[test]
+    fn partial_response_if_range() {
+        let req = Request::builder(Method::GET, "/video")
+            .header(Header::new("Range", "bytes=0-1"))
+            .header(Header::new("If-Range", "\"v1\""))
+            .build()
+            .unwrap();
+
+        let result = Response::partial(&req, b"0123".to_vec(), "video/mp4", Some("\"v1\""));
+        assert_eq!(result.get_status(), &Status::PartialContent);
+
+        let result = Response::partial(&req, b"0123".to_vec(), "video/mp4", Some("\"v2\""));
+        assert_eq!(result.get_status(), &Status::Ok);
+        assert_eq!(result.get_body(), b"0123");
+    }
+
+    #[test]
+    fn finalize_adds_date_and_content_length() {
+        let result = Response::body("Hello").finalize(None).unwrap();
+
+        assert!(result.headers().contains("Date"));
+        assert_eq!(result.headers().get("Content-Length"), Some("5"));
+        assert_eq!(result.get_body(), b"Hello");
+    }
+
+    #[test]
+    fn finalize_removes_body() {
+        let req = Request::builder(Method::HEAD, "/").build().unwrap();
+        let result = Response::html("Hello").finalize(Some(&req)).unwrap();
+
+        assert_eq!(result.headers().get("Content-Length"), Some("5"));
+        assert!(result.get_body().is_empty());
+
+        let result = Response::html("Hello")
+            .status(Status::NoContent)
+            .to_bytes_for(None)
+            .unwrap();
+
+        assert!(result.ends_with(b"\r\n\r\n"));
+        assert!(!String::from_utf8(result)
+            .unwrap()
+            .contains("Content-Length"));
+
+        let result = Response::chunked()
+            .status(Status::NotModified)
+            .finalize(None)
+            .unwrap();
+
+        assert!(!result.headers().contains("Transfer-Encoding"));
+        assert!(result.to_bytes().ends_with(b"\r\n\r\n"));
+    }
+
+    #[test]
+    fn finalize_head_without_body() {
+        let req = Request::builder(Method::HEAD, "/").build().unwrap();
+        let result = Response::empty().finalize(Some(&req)).unwrap();
+
+        assert!(!result.headers().contains("Content-Length"));
+        assert!(result.get_body().is_empty());
+    }
+
+    #[test]
+    fn finalize_rejects_contradictory_framing() {
+        let result = Response::html("Hello")
+            .header(Header::new("Transfer-Encoding", "chunked"))
+            .finalize(None)
+            .unwrap_err();
+
+        assert_eq!(result.err_type, ErrorType::InvalidFraming);
+
+        let result = Response::body("Hello")
+            .header(Header::new("Content-Length", "5"))
+            .header(Header::new("Content-Length", "6"))
+            .finalize(None);
+
+        assert!(result.is_err());
+
+        let result = Response::body("Hello")
+            .header(Header::new("Content-Length", "4"))
+            .finalize(None);
+
+        assert!(result.is_err());
+
+        let result = Response::body("Hello")
+            .header(Header::new("Content-Length", "+5"))
+            .finalize(None);
+
+        assert!(result.is_err());
+
+        let result = Response::empty()
+            .header(Header::new("Content-Length", "100"))
+            .finalize(None);
+
+        assert!(result.is_err());
+
+        let req = Request::builder(Method::HEAD, "/").build().unwrap();
+        let result = Response::empty()
+            .header(Header::new("Content-Length", "100"))
+            .finalize(Some(&req))
+            .unwrap();
+
+        assert_eq!(result.headers().get("Content-Length"), Some("100"));
+    }
 }
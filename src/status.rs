@@ -1,49 +1,356 @@
+use std::fmt;
+
+use crate::error::{Error, ErrorType};
+use crate::header::is_value_char;
+
+/// A http status code. Every status in the IANA registry has its own variant, and any other code
+/// can be used with [Status::Custom].
 #[derive(Debug, PartialEq, Clone)]
 pub enum Status {
+    Continue,
+    SwitchingProtocols,
+    Processing,
+    EarlyHints,
     Ok,
+    Created,
+    Accepted,
+    NonAuthoritativeInformation,
+    NoContent,
+    ResetContent,
+    PartialContent,
+    MultiStatus,
+    AlreadyReported,
+    ImUsed,
+    MultipleChoices,
+    MovedPermanently,
+    Found,
     SeeOther,
-    NotFound,
-    InternalServerError,
+    NotModified,
+    UseProxy,
+    TemporaryRedirect,
+    PermanentRedirect,
     BadRequest,
     Unauthorized,
+    PaymentRequired,
     Forbidden,
+    NotFound,
     NotAllowed,
+    NotAcceptable,
+    ProxyAuthenticationRequired,
+    RequestTimeout,
+    Conflict,
+    Gone,
+    LengthRequired,
+    PreconditionFailed,
+    ContentTooLarge,
+    UriTooLong,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    ExpectationFailed,
+    MisdirectedRequest,
+    UnprocessableContent,
+    Locked,
+    FailedDependency,
+    TooEarly,
+    UpgradeRequired,
+    PreconditionRequired,
+    TooManyRequests,
+    RequestHeaderFieldsTooLarge,
+    UnavailableForLegalReasons,
+    InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    HttpVersionNotSupported,
+    VariantAlsoNegotiates,
+    InsufficientStorage,
+    LoopDetected,
+    NotExtended,
+    NetworkAuthenticationRequired,
+    /// A status code and reason phrase which aren't in the registry.
+    Custom(u16, String),
 }
 
 impl Status {
+    /// Get the status for a numeric code. Codes which aren't registered become a [Status::Custom]
+    /// with an empty reason phrase. Fails if the code is outside the range `100..=599`.
+    pub fn from_code(code: u16) -> Result<Self, Error> {
+        let code_err = Error {
+            err_type: ErrorType::InvalidCode,
+            msg: format!("Invalid http status code {}", code),
+        };
+
+        match code {
+            100 => Ok(Status::Continue),
+            101 => Ok(Status::SwitchingProtocols),
+            102 => Ok(Status::Processing),
+            103 => Ok(Status::EarlyHints),
+            200 => Ok(Status::Ok),
+            201 => Ok(Status::Created),
+            202 => Ok(Status::Accepted),
+            203 => Ok(Status::NonAuthoritativeInformation),
+            204 => Ok(Status::NoContent),
+            205 => Ok(Status::ResetContent),
+            206 => Ok(Status::PartialContent),
+            207 => Ok(Status::MultiStatus),
+            208 => Ok(Status::AlreadyReported),
+            226 => Ok(Status::ImUsed),
+            300 => Ok(Status::MultipleChoices),
+            301 => Ok(Status::MovedPermanently),
+            302 => Ok(Status::Found),
+            303 => Ok(Status::SeeOther),
+            304 => Ok(Status::NotModified),
+            305 => Ok(Status::UseProxy),
+            307 => Ok(Status::TemporaryRedirect),
+            308 => Ok(Status::PermanentRedirect),
+            400 => Ok(Status::BadRequest),
+            401 => Ok(Status::Unauthorized),
+            402 => Ok(Status::PaymentRequired),
+            403 => Ok(Status::Forbidden),
+            404 => Ok(Status::NotFound),
+            405 => Ok(Status::NotAllowed),
+            406 => Ok(Status::NotAcceptable),
+            407 => Ok(Status::ProxyAuthenticationRequired),
+            408 => Ok(Status::RequestTimeout),
+            409 => Ok(Status::Conflict),
+            410 => Ok(Status::Gone),
+            411 => Ok(Status::LengthRequired),
+            412 => Ok(Status::PreconditionFailed),
+            413 => Ok(Status::ContentTooLarge),
+            414 => Ok(Status::UriTooLong),
+            415 => Ok(Status::UnsupportedMediaType),
+            416 => Ok(Status::RangeNotSatisfiable),
+            417 => Ok(Status::ExpectationFailed),
+            421 => Ok(Status::MisdirectedRequest),
+            422 => Ok(Status::UnprocessableContent),
+            423 => Ok(Status::Locked),
+            424 => Ok(Status::FailedDependency),
+            425 => Ok(Status::TooEarly),
+            426 => Ok(Status::UpgradeRequired),
+            428 => Ok(Status::PreconditionRequired),
+            429 => Ok(Status::TooManyRequests),
+            431 => Ok(Status::RequestHeaderFieldsTooLarge),
+            451 => Ok(Status::UnavailableForLegalReasons),
+            500 => Ok(Status::InternalServerError),
+            501 => Ok(Status::NotImplemented),
+            502 => Ok(Status::BadGateway),
+            503 => Ok(Status::ServiceUnavailable),
+            504 => Ok(Status::GatewayTimeout),
+            505 => Ok(Status::HttpVersionNotSupported),
+            506 => Ok(Status::VariantAlsoNegotiates),
+            507 => Ok(Status::InsufficientStorage),
+            508 => Ok(Status::LoopDetected),
+            510 => Ok(Status::NotExtended),
+            511 => Ok(Status::NetworkAuthenticationRequired),
+            code if (100..=599).contains(&code) => Ok(Status::Custom(code, String::new())),
+            _ => Err(code_err),
+        }
+    }
+
+    /// Create a [Status::Custom] with the given code and reason phrase. Fails if the code is
+    /// outside the range `100..=599`, or the reason contains anything but visible characters,
+    /// spaces and tabs.
+    pub fn custom(code: u16, reason: &str) -> Result<Self, Error> {
+        let code_err = Error {
+            err_type: ErrorType::InvalidCode,
+            msg: format!("Invalid http status code {}", code),
+        };
+
+        let reason_err = Error {
+            err_type: ErrorType::InvalidCode,
+            msg: format!("Invalid reason phrase '{}'", reason.escape_debug()),
+        };
+
+        if !(100..=599).contains(&code) {
+            return Err(code_err);
+        }
+
+        if !reason.chars().all(is_value_char) {
+            return Err(reason_err);
+        }
+
+        Ok(Status::Custom(code, reason.to_string()))
+    }
+
     /// Get the numeric representation of the status code.
-    fn code(&self) -> u16 {
+    pub fn code(&self) -> u16 {
         match self {
+            Status::Continue => 100,
+            Status::SwitchingProtocols => 101,
+            Status::Processing => 102,
+            Status::EarlyHints => 103,
             Status::Ok => 200,
+            Status::Created => 201,
+            Status::Accepted => 202,
+            Status::NonAuthoritativeInformation => 203,
+            Status::NoContent => 204,
+            Status::ResetContent => 205,
+            Status::PartialContent => 206,
+            Status::MultiStatus => 207,
+            Status::AlreadyReported => 208,
+            Status::ImUsed => 226,
+            Status::MultipleChoices => 300,
+            Status::MovedPermanently => 301,
+            Status::Found => 302,
             Status::SeeOther => 303,
+            Status::NotModified => 304,
+            Status::UseProxy => 305,
+            Status::TemporaryRedirect => 307,
+            Status::PermanentRedirect => 308,
             Status::BadRequest => 400,
             Status::Unauthorized => 401,
+            Status::PaymentRequired => 402,
             Status::Forbidden => 403,
             Status::NotFound => 404,
             Status::NotAllowed => 405,
+            Status::NotAcceptable => 406,
+            Status::ProxyAuthenticationRequired => 407,
+            Status::RequestTimeout => 408,
+            Status::Conflict => 409,
+            Status::Gone => 410,
+            Status::LengthRequired => 411,
+            Status::PreconditionFailed => 412,
+            Status::ContentTooLarge => 413,
+            Status::UriTooLong => 414,
+            Status::UnsupportedMediaType => 415,
+            Status::RangeNotSatisfiable => 416,
+            Status::ExpectationFailed => 417,
+            Status::MisdirectedRequest => 421,
+            Status::UnprocessableContent => 422,
+            Status::Locked => 423,
+            Status::FailedDependency => 424,
+            Status::TooEarly => 425,
+            Status::UpgradeRequired => 426,
+            Status::PreconditionRequired => 428,
+            Status::TooManyRequests => 429,
+            Status::RequestHeaderFieldsTooLarge => 431,
+            Status::UnavailableForLegalReasons => 451,
             Status::InternalServerError => 500,
+            Status::NotImplemented => 501,
+            Status::BadGateway => 502,
+            Status::ServiceUnavailable => 503,
+            Status::GatewayTimeout => 504,
+            Status::HttpVersionNotSupported => 505,
+            Status::VariantAlsoNegotiates => 506,
+            Status::InsufficientStorage => 507,
+            Status::LoopDetected => 508,
+            Status::NotExtended => 510,
+            Status::NetworkAuthenticationRequired => 511,
+            Status::Custom(code, _) => *code,
         }
     }
 
-    fn message(&self) -> &str {
-        // Get the status message.
+    /// Get the reason phrase, as given in RFC 9110.
+    pub fn reason(&self) -> &str {
         match self {
+            Status::Continue => "Continue",
+            Status::SwitchingProtocols => "Switching Protocols",
+            Status::Processing => "Processing",
+            Status::EarlyHints => "Early Hints",
             Status::Ok => "OK",
-            Status::SeeOther => "SEE OTHER",
-            Status::BadRequest => "BAD REQUEST",
-            Status::Unauthorized => "UNAUTHORIZED",
-            Status::Forbidden => "FORBIDDEN",
-            Status::NotFound => "NOT FOUND",
-            Status::NotAllowed => "NOT ALLOWED",
-            Status::InternalServerError => "INTERNAL SERVER ERROR",
+            Status::Created => "Created",
+            Status::Accepted => "Accepted",
+            Status::NonAuthoritativeInformation => "Non-Authoritative Information",
+            Status::NoContent => "No Content",
+            Status::ResetContent => "Reset Content",
+            Status::PartialContent => "Partial Content",
+            Status::MultiStatus => "Multi-Status",
+            Status::AlreadyReported => "Already Reported",
+            Status::ImUsed => "IM Used",
+            Status::MultipleChoices => "Multiple Choices",
+            Status::MovedPermanently => "Moved Permanently",
+            Status::Found => "Found",
+            Status::SeeOther => "See Other",
+            Status::NotModified => "Not Modified",
+            Status::UseProxy => "Use Proxy",
+            Status::TemporaryRedirect => "Temporary Redirect",
+            Status::PermanentRedirect => "Permanent Redirect",
+            Status::BadRequest => "Bad Request",
+            Status::Unauthorized => "Unauthorized",
+            Status::PaymentRequired => "Payment Required",
+            Status::Forbidden => "Forbidden",
+            Status::NotFound => "Not Found",
+            Status::NotAllowed => "Method Not Allowed",
+            Status::NotAcceptable => "Not Acceptable",
+            Status::ProxyAuthenticationRequired => "Proxy Authentication Required",
+            Status::RequestTimeout => "Request Timeout",
+            Status::Conflict => "Conflict",
+            Status::Gone => "Gone",
+            Status::LengthRequired => "Length Required",
+            Status::PreconditionFailed => "Precondition Failed",
+            Status::ContentTooLarge => "Content Too Large",
+            Status::UriTooLong => "URI Too Long",
+            Status::UnsupportedMediaType => "Unsupported Media Type",
+            Status::RangeNotSatisfiable => "Range Not Satisfiable",
+            Status::ExpectationFailed => "Expectation Failed",
+            Status::MisdirectedRequest => "Misdirected Request",
+            Status::UnprocessableContent => "Unprocessable Content",
+            Status::Locked => "Locked",
+            Status::FailedDependency => "Failed Dependency",
+            Status::TooEarly => "Too Early",
+            Status::UpgradeRequired => "Upgrade Required",
+            Status::PreconditionRequired => "Precondition Required",
+            Status::TooManyRequests => "Too Many Requests",
+            Status::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            Status::UnavailableForLegalReasons => "Unavailable For Legal Reasons",
+            Status::InternalServerError => "Internal Server Error",
+            Status::NotImplemented => "Not Implemented",
+            Status::BadGateway => "Bad Gateway",
+            Status::ServiceUnavailable => "Service Unavailable",
+            Status::GatewayTimeout => "Gateway Timeout",
+            Status::HttpVersionNotSupported => "HTTP Version Not Supported",
+            Status::VariantAlsoNegotiates => "Variant Also Negotiates",
+            Status::InsufficientStorage => "Insufficient Storage",
+            Status::LoopDetected => "Loop Detected",
+            Status::NotExtended => "Not Extended",
+            Status::NetworkAuthenticationRequired => "Network Authentication Required",
+            Status::Custom(_, reason) => reason,
         }
     }
+
+    /// Whether the status is informational, i.e. `1xx`.
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.code())
+    }
+
+    /// Whether the status is successful, i.e. `2xx`.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code())
+    }
+
+    /// Whether the status is a redirection, i.e. `3xx`.
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.code())
+    }
+
+    /// Whether the status is a client error, i.e. `4xx`.
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.code())
+    }
+
+    /// Whether the status is a server error, i.e. `5xx`.
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.code())
+    }
 }
 
-impl ToString for Status {
-    /// Convert the `Status` to a valid http plaintext representation.
-    fn to_string(&self) -> String {
-        format!("{} {}", self.code(), self.message())
+impl fmt::Display for Status {
+    /// Convert the `Status` to a valid http plaintext representation. A [Status::Custom] with a
+    /// code outside `100..=599` is written as `500 Internal Server Error`, and any characters not
+    /// allowed in its reason phrase are replaced with spaces, so it can't inject headers.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !(100..=599).contains(&self.code()) {
+            return write!(f, "{}", Status::InternalServerError);
+        }
+
+        let reason: String = self
+            .reason()
+            .chars()
+            .map(|x| if is_value_char(x) { x } else { ' ' })
+            .collect();
+
+        write!(f, "{} {}", self.code(), reason)
     }
 }
 
@@ -60,9 +367,9 @@ mod tests {
     }
 
     #[test]
-    fn status_message() {
-        let result = Status::InternalServerError.message();
-        let expected = "INTERNAL SERVER ERROR";
+    fn status_reason() {
+        let result = Status::InternalServerError.reason();
+        let expected = "Internal Server Error";
 
         assert_eq!(result, expected);
     }
@@ -70,8 +377,51 @@ mod tests {
     #[test]
     fn string_representation() {
         let result = Status::NotFound.to_string();
-        let expected = "404 NOT FOUND".to_string();
+        let expected = "404 Not Found".to_string();
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn checked_custom_status() {
+        assert_eq!(
+            Status::custom(299, "Fine").unwrap(),
+            Status::Custom(299, "Fine".to_string())
+        );
+        assert!(Status::custom(299, "OK\r\nSet-Cookie: pwn=1").is_err());
+        assert!(Status::custom(1000, "Big").is_err());
+
+        let result = Status::Custom(299, "OK\r\nSet-Cookie: pwn=1".to_string()).to_string();
+        assert_eq!(result, "299 OK  Set-Cookie: pwn=1");
+
+        let result = Status::Custom(1000, "Big".to_string()).to_string();
+        assert_eq!(result, "500 Internal Server Error");
+    }
+
+    #[test]
+    fn status_from_code() {
+        assert_eq!(Status::from_code(429), Ok(Status::TooManyRequests));
+        assert_eq!(
+            Status::from_code(299),
+            Ok(Status::Custom(299, String::new()))
+        );
+        assert!(Status::from_code(600).is_err());
+    }
+
+    #[test]
+    fn custom_status() {
+        let result = Status::Custom(599, "Network Connect Timeout".to_string());
+
+        assert_eq!(result.to_string(), "599 Network Connect Timeout");
+        assert!(result.is_server_error());
+    }
+
+    #[test]
+    fn status_class() {
+        assert!(Status::Continue.is_informational());
+        assert!(Status::NoContent.is_success());
+        assert!(Status::PermanentRedirect.is_redirection());
+        assert!(Status::UnprocessableContent.is_client_error());
+        assert!(!Status::BadGateway.is_client_error());
+    }
 }
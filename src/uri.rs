@@ -0,0 +1,297 @@
+use std::fmt;
+
+use crate::error::{Error, ErrorType};
+
+/// The form of a request target, as defined in RFC 9112.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TargetForm {
+    /// A path and optional query, e.g. `/hello?name=foo`. Used by most requests.
+    Origin,
+    /// A full uri, e.g. `http://localhost:8080/hello`. Used by requests to proxies.
+    Absolute,
+    /// A host and port, e.g. `localhost:443`. Used by `CONNECT` requests.
+    Authority,
+    /// A single `*`. Used by server-wide `OPTIONS` requests.
+    Asterisk,
+}
+
+/// A parsed request target. The query is kept percent-encoded, see [crate::Path] for the decoded
+/// query pairs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Uri {
+    pub form: TargetForm,
+    pub scheme: Option<String>,
+    /// The host, without the brackets around an IPv6 literal.
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Uri {
+    /// Parse a request target in any of the four forms. A request target never has a fragment, so
+    /// a `#` is an error.
+    pub fn from_string(target: &str) -> Result<Self, Error> {
+        Self::parse(target, false)
+    }
+
+    /// Parse a uri in any of the four forms, allowing a fragment, e.g. one given to
+    /// [crate::RequestBuilder]. The fragment is never sent as part of a request.
+    pub(crate) fn with_fragment(target: &str) -> Result<Self, Error> {
+        Self::parse(target, true)
+    }
+
+    fn parse(target: &str, allow_fragment: bool) -> Result<Self, Error> {
+        let target_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid request target".to_string(),
+        };
+
+        if !allow_fragment && target.contains('#') {
+            return Err(target_err);
+        }
+
+        if target == "*" {
+            return Ok(Uri {
+                form: TargetForm::Asterisk,
+                path: "*".to_string(),
+                ..Self::empty()
+            });
+        }
+
+        if target.starts_with('/') {
+            return Ok(Self::origin(target));
+        }
+
+        if let Some((scheme, rest)) = target.split_once("://") {
+            if !Self::is_scheme(scheme) {
+                return Err(target_err);
+            }
+
+            let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+            let (host, port) = Self::parse_authority(&rest[..authority_end])?;
+
+            let mut uri = Self::origin(&rest[authority_end..]);
+
+            if uri.path.is_empty() {
+                uri.path = "/".to_string();
+            }
+
+            return Ok(Uri {
+                form: TargetForm::Absolute,
+                scheme: Some(scheme.to_ascii_lowercase()),
+                host: Some(host),
+                port,
+                ..uri
+            });
+        }
+
+        match Self::parse_authority(target)? {
+            (host, Some(port)) => Ok(Uri {
+                form: TargetForm::Authority,
+                host: Some(host),
+                port: Some(port),
+                ..Self::empty()
+            }),
+            (_, None) => Err(target_err),
+        }
+    }
+
+    /// Split an origin-form target into its path, query and fragment, without validating it. Used
+    /// for lenient parsing and for uris which may have a fragment.
+    pub(crate) fn origin(target: &str) -> Self {
+        let (target, fragment) = match target.split_once('#') {
+            Some((target, fragment)) => (target, Some(fragment.to_string())),
+            None => (target, None),
+        };
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+
+        Uri {
+            path: path.to_string(),
+            query,
+            fragment,
+            ..Self::empty()
+        }
+    }
+
+    /// Get the authority of the uri, i.e. the host and port, e.g. `localhost:8080`.
+    pub fn authority(&self) -> Option<String> {
+        let host = self.host.as_ref()?;
+
+        let host = if host.contains(':') {
+            format!("[{}]", host)
+        } else {
+            host.to_string()
+        };
+
+        match self.port {
+            Some(port) => Some(format!("{}:{}", host, port)),
+            None => Some(host),
+        }
+    }
+
+    fn empty() -> Self {
+        Uri {
+            form: TargetForm::Origin,
+            scheme: None,
+            host: None,
+            port: None,
+            path: String::new(),
+            query: None,
+            fragment: None,
+        }
+    }
+
+    fn is_scheme(scheme: &str) -> bool {
+        scheme.starts_with(|x: char| x.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || "+-.".contains(x))
+    }
+
+    fn parse_authority(authority: &str) -> Result<(String, Option<u16>), Error> {
+        let authority_err = Error {
+            err_type: ErrorType::ParserError,
+            msg: "Invalid authority in request target".to_string(),
+        };
+
+        // Any userinfo is dropped, it has been deprecated for http uris.
+        let authority = match authority.rsplit_once('@') {
+            Some((_, authority)) => authority,
+            None => authority,
+        };
+
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(rest) => match rest.split_once(']') {
+                Some((host, "")) if host.contains(':') => (host, None),
+                Some((host, port)) if host.contains(':') => match port.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return Err(authority_err),
+                },
+                _ => return Err(authority_err),
+            },
+            None => match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+
+        let valid_host = host
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || "-._~%!$&'()*+,;=:".contains(x));
+
+        if host.is_empty() || !valid_host {
+            return Err(authority_err);
+        }
+
+        let port = match port {
+            Some("") | None => None,
+            Some(port) if port.chars().all(|x| x.is_ascii_digit()) => {
+                Some(port.parse().map_err(|_| authority_err)?)
+            }
+            Some(_) => return Err(authority_err),
+        };
+
+        Ok((host.to_string(), port))
+    }
+}
+
+impl fmt::Display for Uri {
+    /// Convert the `Uri` back to a request target, in the same form it was parsed from.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.form {
+            TargetForm::Asterisk => return write!(f, "*"),
+            TargetForm::Authority => {
+                return write!(f, "{}", self.authority().unwrap_or_default());
+            }
+            TargetForm::Absolute => write!(
+                f,
+                "{}://{}",
+                self.scheme.as_deref().unwrap_or("http"),
+                self.authority().unwrap_or_default()
+            )?,
+            TargetForm::Origin => (),
+        }
+
+        write!(f, "{}", self.path)?;
+
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TargetForm, Uri};
+
+    #[test]
+    fn parse_origin_form() {
+        let result = Uri::from_string("/hello?name=foo").unwrap();
+
+        assert_eq!(result.form, TargetForm::Origin);
+        assert_eq!(result.path, "/hello");
+        assert_eq!(result.query, Some("name=foo".to_string()));
+        assert_eq!(result.host, None);
+    }
+
+    #[test]
+    fn parse_absolute_form() {
+        let result = Uri::with_fragment("http://localhost:8080/x?y=1#top").unwrap();
+
+        assert_eq!(result.form, TargetForm::Absolute);
+        assert_eq!(result.scheme, Some("http".to_string()));
+        assert_eq!(result.host, Some("localhost".to_string()));
+        assert_eq!(result.port, Some(8080));
+        assert_eq!(result.path, "/x");
+        assert_eq!(result.query, Some("y=1".to_string()));
+        assert_eq!(result.fragment, Some("top".to_string()));
+    }
+
+    #[test]
+    fn parse_absolute_form_with_ipv6_host() {
+        let result = Uri::from_string("http://[::1]:3000").unwrap();
+
+        assert_eq!(result.host, Some("::1".to_string()));
+        assert_eq!(result.port, Some(3000));
+        assert_eq!(result.path, "/");
+        assert_eq!(result.authority(), Some("[::1]:3000".to_string()));
+    }
+
+    #[test]
+    fn parse_authority_form() {
+        let result = Uri::from_string("example.com:443").unwrap();
+
+        assert_eq!(result.form, TargetForm::Authority);
+        assert_eq!(result.host, Some("example.com".to_string()));
+        assert_eq!(result.port, Some(443));
+        assert_eq!(result.to_string(), "example.com:443");
+    }
+
+    #[test]
+    fn parse_asterisk_form() {
+        let result = Uri::from_string("*").unwrap();
+
+        assert_eq!(result.form, TargetForm::Asterisk);
+        assert_eq!(result.to_string(), "*");
+    }
+
+    #[test]
+    fn parse_invalid_target() {
+        assert!(Uri::from_string("example.com").is_err());
+        assert!(Uri::from_string("http://exa mple.com/").is_err());
+        assert!(Uri::from_string("http://localhost:99999/").is_err());
+        assert!(Uri::from_string("/a#b").is_err());
+    }
+}